@@ -0,0 +1,286 @@
+use advent_of_code_2022::{day_08, day_09, day_10, day_11, day_12, day_13, day_14};
+use pico_args::Arguments;
+use std::fs::File;
+use std::io::{stdout, BufWriter};
+use std::path::PathBuf;
+use std::process;
+
+const YEAR: u16 = 2022;
+
+const USAGE: &str = "Usage: cargo visualise <day> [--input <path>] [options]
+
+Day 8 highlights the trees visible from outside the forest, with options:
+  --scores           Colour trees by scenic score instead
+  --ppm <path>       Write a PPM image to <path> instead of the terminal
+  --scale <n>        Size of each tree in the PPM image (default: 5)
+
+Day 9 animates the rope, with options:
+  --knots <n>        Number of knots in the rope (default: 10)
+  --slack <n>        Distance at which knots start following the one before (default: 1)
+  --manhattan        Measure distances without diagonal steps
+  --fps <n>          Maximum frames per second in the terminal (default: 30)
+  --rows <n>         Height of the terminal viewport (default: 40)
+  --cols <n>         Width of the terminal viewport (default: 120)
+  --heat-map <path>  Write a PPM image of the tail's visits to <path> instead
+
+Day 10 draws the CRT's picture, with options:
+  --width <n>        Width of the CRT in pixels (default: 40)
+  --height <n>       Height of the CRT in pixels (default: 6)
+  --sprite <n>       Width of the sprite in pixels (default: 3)
+  --braille          Draw each pixel as a braille dot
+  --pbm <path>       Write a PBM image to <path> instead of the terminal
+  --scale <n>        Size of each pixel in the PBM image (default: 10)
+  --trace            Print the instruction and registers of each cycle instead
+
+Day 11 exports statistics about each round, with options:
+  --part2            Keep worry levels modulo the monkeys' divisors instead of dividing them by 3
+  --rounds <n>       Number of rounds to play (default: 20, or 10000 with --part2)
+  --json             Write JSON instead of CSV
+  --output <path>    Write to <path> instead of the terminal
+
+Day 12 options:
+  --output <path>    Write the shortest routes to <path> instead of the terminal
+  --diagonal         Allow diagonal steps
+  --max-ascent <n>   Highest climb in a single step (default: 1)
+  --max-descent <n>  Deepest descent in a single step (default: 255)
+  --step-cost <n>    Cost of each step (default: 1)
+  --ascent-cost <n>  Extra cost of each unit of elevation climbed (default: 0)
+  --descent-cost <n> Extra cost of each unit of elevation descended (default: 0)
+
+Day 13 prints an explanation of the comparison of each pair of packets.
+
+Day 14 options:
+  --floor            Simulate with the infinite floor (part 2)
+  --step <n>         Number of grains of sand per frame (default: 1)
+  --fps <n>          Maximum frames per second in the terminal (default: 30)
+  --rows <n>         Height of the terminal viewport (default: 40)
+  --cols <n>         Width of the terminal viewport (default: 120)
+  --ppm <dir>        Write PPM frames to <dir> instead of the terminal";
+
+fn visualise_day_08(args: &mut Arguments, data: &str) -> Result<(), String> {
+    let map = if args.contains("--scores") {
+        day_08::ForestMap::ScenicScore
+    } else {
+        day_08::ForestMap::Visibility
+    };
+    let output = match args
+        .opt_value_from_str::<_, PathBuf>("--ppm")
+        .map_err(|e| format!("{e}"))?
+    {
+        Some(path) => day_08::ForestOutput::Ppm {
+            path,
+            scale: args
+                .opt_value_from_str("--scale")
+                .map_err(|e| format!("{e}"))?
+                .unwrap_or(5),
+        },
+        None => day_08::ForestOutput::Terminal,
+    };
+    println!("{}", day_08::render_forest(data, &map, &output)?);
+    Ok(())
+}
+
+fn visualise_day_09(args: &mut Arguments, data: &str) -> Result<(), String> {
+    let num_knots = args
+        .opt_value_from_str("--knots")
+        .map_err(|e| format!("{e}"))?
+        .unwrap_or(10);
+    let slack = args
+        .opt_value_from_str("--slack")
+        .map_err(|e| format!("{e}"))?
+        .unwrap_or(1);
+    if slack < 0 {
+        return Err(format!("The slack must not be negative, got {slack}"));
+    }
+    let follow_rule: Box<dyn day_09::FollowRule> = if args.contains("--manhattan") {
+        Box::new(day_09::Manhattan { slack })
+    } else {
+        Box::new(day_09::Chebyshev { slack })
+    };
+    if let Some(path) = args
+        .opt_value_from_str::<_, PathBuf>("--heat-map")
+        .map_err(|e| format!("{e}"))?
+    {
+        return day_09::write_tail_heat_map(data, num_knots, &*follow_rule, &path);
+    }
+    let animation = day_09::Animation {
+        num_knots,
+        follow_rule,
+        max_fps: args
+            .opt_value_from_str("--fps")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(30),
+        viewport_rows: args
+            .opt_value_from_str("--rows")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(40),
+        viewport_cols: args
+            .opt_value_from_str("--cols")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(120),
+    };
+    let num_positions = animation.run(data)?;
+    println!("The tail visited {num_positions} positions");
+    Ok(())
+}
+
+fn visualise_day_10(args: &mut Arguments, data: &str) -> Result<(), String> {
+    if args.contains("--trace") {
+        println!("{}", day_10::trace(data)?);
+        return Ok(());
+    }
+    let default_geometry = day_10::MonitorGeometry::default();
+    let geometry = day_10::MonitorGeometry {
+        width: args
+            .opt_value_from_str("--width")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(default_geometry.width),
+        height: args
+            .opt_value_from_str("--height")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(default_geometry.height),
+        sprite_width: args
+            .opt_value_from_str("--sprite")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(default_geometry.sprite_width),
+    };
+    let output = match args
+        .opt_value_from_str::<_, PathBuf>("--pbm")
+        .map_err(|e| format!("{e}"))?
+    {
+        Some(path) => day_10::CrtOutput::Pbm {
+            path,
+            scale: args
+                .opt_value_from_str("--scale")
+                .map_err(|e| format!("{e}"))?
+                .unwrap_or(10),
+        },
+        None if args.contains("--braille") => day_10::CrtOutput::Braille,
+        None => day_10::CrtOutput::Text,
+    };
+    day_10::render_crt(data, &geometry, &output)
+}
+
+fn visualise_day_11(args: &mut Arguments, data: &str) -> Result<(), String> {
+    let with_relief = !args.contains("--part2");
+    let statistics = day_11::Statistics {
+        with_relief,
+        rounds: args
+            .opt_value_from_str("--rounds")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(if with_relief { 20 } else { 10_000 }),
+        format: if args.contains("--json") {
+            day_11::StatisticsFormat::Json
+        } else {
+            day_11::StatisticsFormat::Csv
+        },
+    };
+    match args
+        .opt_value_from_str::<_, PathBuf>("--output")
+        .map_err(|e| format!("{e}"))?
+    {
+        Some(path) => {
+            let file = File::create(&path)
+                .map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+            statistics.write(data, &mut BufWriter::new(file))
+        }
+        None => statistics.write(data, &mut stdout().lock()),
+    }
+}
+
+fn visualise_day_12(args: &mut Arguments, data: &str) -> Result<(), String> {
+    let output = match args
+        .opt_value_from_str::<_, PathBuf>("--output")
+        .map_err(|e| format!("{e}"))?
+    {
+        Some(path) => day_12::RouteOutput::File(path),
+        None => day_12::RouteOutput::Terminal,
+    };
+    let default = day_12::MovementPolicy::default();
+    let policy = day_12::MovementPolicy {
+        max_ascent: args
+            .opt_value_from_str("--max-ascent")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(default.max_ascent),
+        max_descent: args
+            .opt_value_from_str("--max-descent")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(default.max_descent),
+        diagonal_moves: args.contains("--diagonal"),
+        step_cost: args
+            .opt_value_from_str("--step-cost")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(default.step_cost),
+        ascent_cost: args
+            .opt_value_from_str("--ascent-cost")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(default.ascent_cost),
+        descent_cost: args
+            .opt_value_from_str("--descent-cost")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(default.descent_cost),
+    };
+    day_12::render_shortest_routes(data, &policy, &output)
+}
+
+fn visualise_day_14(args: &mut Arguments, data: &str) -> Result<(), String> {
+    let output = match args
+        .opt_value_from_str::<_, PathBuf>("--ppm")
+        .map_err(|e| format!("{e}"))?
+    {
+        Some(directory) => day_14::FrameOutput::PpmFrames(directory),
+        None => day_14::FrameOutput::Terminal {
+            max_fps: args
+                .opt_value_from_str("--fps")
+                .map_err(|e| format!("{e}"))?
+                .unwrap_or(30),
+            viewport_rows: args
+                .opt_value_from_str("--rows")
+                .map_err(|e| format!("{e}"))?
+                .unwrap_or(40),
+            viewport_cols: args
+                .opt_value_from_str("--cols")
+                .map_err(|e| format!("{e}"))?
+                .unwrap_or(120),
+        },
+    };
+    let visualisation = day_14::Visualisation {
+        with_infinite_floor: args.contains("--floor"),
+        grains_per_frame: args
+            .opt_value_from_str("--step")
+            .map_err(|e| format!("{e}"))?
+            .unwrap_or(1),
+        output,
+    };
+    let num_grains = visualisation.run(data)?;
+    println!("{num_grains} grains of sand came to rest");
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let mut args = Arguments::from_env();
+    let day: u8 = args.free_from_str().map_err(|e| format!("{e}"))?;
+    let input_path = args
+        .opt_value_from_str("--input")
+        .map_err(|e| format!("{e}"))?
+        .unwrap_or_else(|| format!("input/{YEAR}/day{day}.txt"));
+    let data = std::fs::read_to_string(&input_path)
+        .map_err(|e| format!("Failed to read {input_path}: {e}"))?;
+    match day {
+        8 => visualise_day_08(&mut args, &data),
+        9 => visualise_day_09(&mut args, &data),
+        10 => visualise_day_10(&mut args, &data),
+        11 => visualise_day_11(&mut args, &data),
+        12 => visualise_day_12(&mut args, &data),
+        13 => day_13::explain_pairs(&data).map(|report| println!("{report}")),
+        14 => visualise_day_14(&mut args, &data),
+        _ => Err(format!("No visualisation for day {day}")),
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{e}\n\n{USAGE}");
+        process::exit(1);
+    }
+}
@@ -1,5 +1,7 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::image_writer::{scale_pixels, write_ppm, Rgb};
+use std::path::PathBuf;
 use std::str::FromStr;
 
 struct Forest {
@@ -26,6 +28,67 @@ impl FromStr for Forest {
     }
 }
 
+impl Forest {
+    fn num_rows(&self) -> usize {
+        self.tree_heights.len()
+    }
+    fn num_cols(&self) -> usize {
+        self.tree_heights.first().map_or(0, |row| row.len())
+    }
+    fn height(&self, (row, col): (usize, usize)) -> u8 {
+        self.tree_heights[row][col]
+    }
+    fn to_index(&self, (row, col): (usize, usize)) -> usize {
+        row * self.num_cols() + col
+    }
+    // The positions along each row and column, walked in both directions
+    fn sightlines(&self) -> Vec<Vec<(usize, usize)>> {
+        let (num_rows, num_cols) = (self.num_rows(), self.num_cols());
+        let rows = (0..num_rows).map(|row| (0..num_cols).map(|col| (row, col)).collect::<Vec<_>>());
+        let cols = (0..num_cols).map(|col| (0..num_rows).map(|row| (row, col)).collect::<Vec<_>>());
+        rows.chain(cols)
+            .flat_map(|line| [line.iter().rev().copied().collect(), line])
+            .collect()
+    }
+    // Whether each tree is visible from outside the forest, indexed by position
+    fn visibility_map(&self) -> Vec<bool> {
+        let mut visible = vec![false; self.num_rows() * self.num_cols()];
+        for line in self.sightlines() {
+            let mut max_height = None;
+            for pos in line {
+                let height = self.height(pos);
+                if max_height.is_none_or(|max| height > max) {
+                    max_height = Some(height);
+                    visible[self.to_index(pos)] = true;
+                }
+            }
+        }
+        visible
+    }
+    // The scenic score of each tree, indexed by position
+    fn scenic_scores(&self) -> Vec<usize> {
+        let mut scores = vec![1; self.num_rows() * self.num_cols()];
+        for line in self.sightlines() {
+            // The trees seen so far that aren't hidden behind a taller one, in decreasing height
+            let mut stack: Vec<usize> = Vec::new();
+            for (i, pos) in line.iter().enumerate() {
+                let height = self.height(*pos);
+                while stack
+                    .last()
+                    .is_some_and(|previous| self.height(line[*previous]) < height)
+                {
+                    stack.pop();
+                }
+                // Looking back, the view stops at the nearest tree at least as tall, or the edge
+                let viewing_distance = i - stack.last().unwrap_or(&0);
+                scores[self.to_index(*pos)] *= viewing_distance;
+                stack.push(i);
+            }
+        }
+        scores
+    }
+}
+
 fn list_visible_trees(
     line: impl Iterator<Item = (u8, (usize, usize))>,
 ) -> impl Iterator<Item = (usize, usize)> {
@@ -41,6 +104,124 @@ fn list_visible_trees(
     .map(|(_h, pos)| pos)
 }
 
+impl Forest {
+    // The position and scenic score of the tree with the best scenic score
+    fn best_tree(&self) -> Option<((usize, usize), usize)> {
+        let num_cols = self.num_cols();
+        self.scenic_scores()
+            .into_iter()
+            .enumerate()
+            // Keep the first of equally good trees
+            .rev()
+            .max_by_key(|(_, score)| *score)
+            .map(|(index, score)| ((index / num_cols, index % num_cols), score))
+    }
+    // The colour of each tree in the given map, indexed by position
+    fn colours(&self, map: &ForestMap) -> Vec<Rgb> {
+        match map {
+            ForestMap::Visibility => self
+                .visibility_map()
+                .into_iter()
+                .zip(self.tree_heights.iter().flatten())
+                .map(|(visible, height)| {
+                    if visible {
+                        // Taller trees in brighter green
+                        [40, 100 + 15 * height, 40]
+                    } else {
+                        [50, 40, 35]
+                    }
+                })
+                .collect(),
+            ForestMap::ScenicScore => {
+                let scores = self.scenic_scores();
+                let max_score = scores.iter().copied().max().unwrap_or(0);
+                scores
+                    .into_iter()
+                    .map(|score| heat_colour(score, max_score))
+                    .collect()
+            }
+        }
+    }
+}
+
+// From dark blue for a score of 0, through teal and yellow, to red for the best score
+fn heat_colour(score: usize, max_score: usize) -> Rgb {
+    const STOPS: [Rgb; 4] = [[10, 10, 60], [0, 150, 170], [240, 210, 50], [220, 30, 30]];
+    // Logarithmic, as a few trees have scores orders of magnitude above the rest
+    let heat = if max_score == 0 {
+        0.
+    } else {
+        (score as f64).ln_1p() / (max_score as f64).ln_1p()
+    };
+    let position = heat * (STOPS.len() - 1) as f64;
+    let (from, to) = (
+        STOPS[(position.floor() as usize).min(STOPS.len() - 1)],
+        STOPS[(position.ceil() as usize).min(STOPS.len() - 1)],
+    );
+    let weight = position.fract();
+    [0, 1, 2].map(|i| (from[i] as f64 * (1. - weight) + to[i] as f64 * weight).round() as u8)
+}
+
+pub enum ForestMap {
+    // Trees visible from outside the forest in green, shaded by height
+    Visibility,
+    // Trees coloured by scenic score
+    ScenicScore,
+}
+
+pub enum ForestOutput {
+    // Print the height of each tree on a coloured background, with the best tree in bold
+    Terminal,
+    // Write an image with each tree scaled to a square of scale x scale pixels
+    Ppm { path: PathBuf, scale: usize },
+}
+
+// Render the forest, returning where the best tree is, as in part 2
+pub fn render_forest(data: &str, map: &ForestMap, output: &ForestOutput) -> Result<String, String> {
+    let forest: Forest = data.parse()?;
+    let ((best_row, best_col), best_score) = forest
+        .best_tree()
+        .ok_or_else(|| "Empty forest".to_string())?;
+    let colours = forest.colours(map);
+    let num_cols = forest.num_cols();
+    match output {
+        ForestOutput::Terminal => {
+            let rendered = forest
+                .tree_heights
+                .iter()
+                .enumerate()
+                .map(|(row, heights)| {
+                    heights
+                        .iter()
+                        .enumerate()
+                        .map(|(col, height)| {
+                            let [r, g, b] = colours[row * num_cols + col];
+                            let bold = if (row, col) == (best_row, best_col) {
+                                "\x1b[1m"
+                            } else {
+                                ""
+                            };
+                            format!("\x1b[48;2;{r};{g};{b}m\x1b[97m{bold}{height}\x1b[0m")
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            println!("{rendered}");
+        }
+        ForestOutput::Ppm { path, scale } => {
+            let scale = (*scale).max(1);
+            let num_rows = forest.num_rows();
+            let pixels = scale_pixels(num_cols, num_rows, scale, &colours);
+            write_ppm(path, num_cols * scale, num_rows * scale, &pixels)
+                .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        }
+    }
+    Ok(format!(
+        "Best tree at row {best_row}, column {best_col}, with a scenic score of {best_score}"
+    ))
+}
+
 type Parsed = Forest;
 type Input = Forest;
 type Output = usize;
@@ -99,6 +280,14 @@ fn part1(data: &Input) -> Output {
     all.len()
 }
 
+#[aoc(day8, part1, Sweeps)]
+fn part1_sweeps(data: &Input) -> Output {
+    data.visibility_map()
+        .into_iter()
+        .filter(|visible| *visible)
+        .count()
+}
+
 fn count_visible_trees_from_pos(this_height: u8, next_trees: impl Iterator<Item = u8>) -> usize {
     let mut count = 0;
     for height in next_trees {
@@ -151,9 +340,15 @@ fn part2(data: &Input) -> Output {
         .unwrap()
 }
 
+#[aoc(day8, part2, MonotonicStack)]
+fn part2_monotonic_stack(data: &Input) -> Output {
+    data.scenic_scores().into_iter().max().unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::random::XorShift;
 
     const EXAMPLE_SOLUTION_PART1: Output = 21;
     const SOLUTION_PART1: Output = 1835;
@@ -182,4 +377,90 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input()), SOLUTION_PART2)
     }
+    #[test]
+    fn test_part1_sweeps_given_example_input() {
+        assert_eq!(part1_sweeps(&example_input()), EXAMPLE_SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part1_sweeps() {
+        assert_eq!(part1_sweeps(&input()), SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part2_monotonic_stack_given_example_input() {
+        assert_eq!(
+            part2_monotonic_stack(&example_input()),
+            EXAMPLE_SOLUTION_PART2
+        )
+    }
+    #[test]
+    fn test_part2_monotonic_stack() {
+        assert_eq!(part2_monotonic_stack(&input()), SOLUTION_PART2)
+    }
+    fn random_forest(
+        rng: &mut XorShift,
+        num_rows: usize,
+        num_cols: usize,
+        max_height: u64,
+    ) -> Forest {
+        Forest {
+            tree_heights: (0..num_rows)
+                .map(|_| {
+                    (0..num_cols)
+                        .map(|_| rng.below(max_height + 1) as u8)
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+    #[test]
+    fn test_agreement_on_random_forests() {
+        let mut rng = XorShift::new(8);
+        for _ in 0..200 {
+            let num_rows = 1 + rng.below(30) as usize;
+            let num_cols = 1 + rng.below(30) as usize;
+            // Few distinct heights make for many ties
+            let max_height = [1, 3, 9][rng.below(3) as usize];
+            let forest = random_forest(&mut rng, num_rows, num_cols, max_height);
+            assert_eq!(part1(&forest), part1_sweeps(&forest));
+            let scores = forest.scenic_scores();
+            for row in 0..num_rows {
+                for col in 0..num_cols {
+                    assert_eq!(
+                        scores[forest.to_index((row, col))],
+                        scenic_score((row, col), &forest),
+                        "{num_rows}x{num_cols} forest, at {:?}",
+                        (row, col)
+                    );
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_best_tree() {
+        // The tree of height 5 in the middle of the fourth row
+        assert_eq!(example_input().best_tree(), Some(((3, 2), 8)));
+    }
+    #[test]
+    fn test_colours() {
+        let forest = example_input();
+        let visible = forest.colours(&ForestMap::Visibility);
+        assert_eq!(visible.iter().filter(|c| **c != [50, 40, 35]).count(), 21);
+        // The tree of height 3 in the centre of the example is hidden
+        assert_eq!(visible[forest.to_index((2, 2))], [50, 40, 35]);
+        let scores = forest.colours(&ForestMap::ScenicScore);
+        assert_eq!(scores[forest.to_index((0, 0))], [10, 10, 60]);
+        assert_eq!(scores[forest.to_index((3, 2))], [220, 30, 30]);
+    }
+    #[test]
+    fn test_render_forest() {
+        let summary = render_forest(
+            include_str!("../example/2022/day8.txt"),
+            &ForestMap::ScenicScore,
+            &ForestOutput::Terminal,
+        );
+        assert_eq!(
+            summary,
+            Ok("Best tree at row 3, column 2, with a scenic score of 8".to_string())
+        );
+    }
 }
@@ -1,86 +1,130 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::frame_limiter::FrameLimiter;
+use crate::image_writer::{write_ppm, Rgb};
 use crate::input_parser::parse_line_vec;
-use generic_array::{typenum::U1, typenum::U9, ArrayLength, GenericArray};
+use bit_set::BitSet;
 use std::collections::BTreeSet;
 use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::iter::once;
+use std::path::Path;
 use std::str::FromStr;
 
 type Position = (i32, i32);
 
-#[derive(Debug, Default)]
-struct RopeLink {
-    head: Position,
-    tail: Position,
-}
-
-impl RopeLink {
-    fn move_head(&mut self, d: Direction) {
-        let m = match d {
-            Direction::Up => (0, 1),
-            Direction::Down => (0, -1),
-            Direction::Right => (1, 0),
-            Direction::Left => (-1, 0),
-        };
-        self.head.0 += m.0;
-        self.head.1 += m.1;
-    }
-    fn move_tail(&mut self) {
-        let distance = (
-            (self.head.0 - self.tail.0).abs(),
-            (self.head.1 - self.tail.1).abs(),
-        );
-        if distance.0 > 1 && self.head.1 == self.tail.1 {
-            if self.head.0 > self.tail.0 {
-                self.tail.0 += 1;
-            } else {
-                self.tail.0 -= 1;
-            }
-        } else if self.head.0 == self.tail.0 && distance.1 > 1 {
-            if self.head.1 > self.tail.1 {
-                self.tail.1 += 1;
-            } else {
-                self.tail.1 -= 1;
-            }
-        } else if distance.0 > 1 || distance.1 > 1 {
-            if self.head.0 > self.tail.0 {
-                self.tail.0 += 1;
-            } else {
-                self.tail.0 -= 1;
+// How a knot follows the one before it
+pub trait FollowRule: Debug {
+    // How far a knot is from the one before it, given the difference of their coordinates
+    fn distance(&self, delta: Position) -> i32;
+    // The largest distance at which a knot doesn't need to move
+    fn slack(&self) -> i32;
+    // One step towards the knot before, diagonal by default
+    fn step(&self, delta: Position) -> Position {
+        (delta.0.signum(), delta.1.signum())
+    }
+    // Step towards the knot before until within the slack, or until there's no step left to take
+    fn follow(&self, head: Position, mut tail: Position) -> Position {
+        loop {
+            let delta = (head.0 - tail.0, head.1 - tail.1);
+            if self.distance(delta) <= self.slack() {
+                return tail;
             }
-            if self.head.1 > self.tail.1 {
-                self.tail.1 += 1;
-            } else {
-                self.tail.1 -= 1;
+            let step = self.step(delta);
+            if step == (0, 0) {
+                return tail;
             }
+            tail = (tail.0 + step.0, tail.1 + step.1);
         }
-        let distance = (
-            (self.head.0 - self.tail.0).abs(),
-            (self.head.1 - self.tail.1).abs(),
-        );
-        assert!(distance.0 <= 1);
-        assert!(distance.1 <= 1);
     }
 }
 
-#[derive(Debug, Default)]
-struct Rope<N: ArrayLength<RopeLink>> {
-    links: GenericArray<RopeLink, N>,
+// Knots may be apart by up to `slack` cells along each axis. With a slack of 1, this is the
+// puzzle's rule: knots must touch, including diagonally.
+#[derive(Debug)]
+pub struct Chebyshev {
+    pub slack: i32,
+}
+
+impl FollowRule for Chebyshev {
+    fn distance(&self, delta: Position) -> i32 {
+        delta.0.abs().max(delta.1.abs())
+    }
+    fn slack(&self) -> i32 {
+        self.slack
+    }
+}
+
+// Knots may be apart by up to `slack` horizontal and vertical steps, and only move along one axis
+// at a time, closing the largest gap first
+#[derive(Debug)]
+pub struct Manhattan {
+    pub slack: i32,
 }
 
-impl<N> Rope<N>
-where
-    N: ArrayLength<RopeLink>,
-{
+impl FollowRule for Manhattan {
+    fn distance(&self, delta: Position) -> i32 {
+        delta.0.abs() + delta.1.abs()
+    }
+    fn slack(&self) -> i32 {
+        self.slack
+    }
+    fn step(&self, delta: Position) -> Position {
+        if delta.0.abs() >= delta.1.abs() {
+            (delta.0.signum(), 0)
+        } else {
+            (0, delta.1.signum())
+        }
+    }
+}
+
+pub const PUZZLE_RULE: Chebyshev = Chebyshev { slack: 1 };
+
+// The knots of a rope, from its head to its tail
+#[derive(Debug)]
+struct Rope<'a> {
+    knots: Vec<Position>,
+    rule: &'a dyn FollowRule,
+}
+
+impl<'a> Rope<'a> {
+    fn new(num_knots: usize, rule: &'a dyn FollowRule) -> Self {
+        assert!(num_knots > 0, "A rope needs at least one knot");
+        Self {
+            knots: vec![(0, 0); num_knots],
+            rule,
+        }
+    }
+    fn move_head(&mut self, d: Direction) {
+        let m = d.offset();
+        self.knots[0].0 += m.0;
+        self.knots[0].1 += m.1;
+    }
+    // Move a knot towards the one before it, if they're too far apart
+    fn follow(&self, head: Position, tail: Position) -> Position {
+        self.rule.follow(head, tail)
+    }
     // Move one step and return the tail position
     fn move_one_step(&mut self, d: Direction) -> Position {
-        self.links[0].move_head(d);
-        self.links[0].move_tail();
-        for i in 1..self.links.len() {
-            self.links[i].head = self.links[i - 1].tail;
-            self.links[i].move_tail();
+        self.move_head(d);
+        for i in 1..self.knots.len() {
+            let knot = self.follow(self.knots[i - 1], self.knots[i]);
+            if knot == self.knots[i] {
+                // The knots behind a knot that didn't move have nothing to follow
+                break;
+            }
+            self.knots[i] = knot;
+        }
+        self.knots[self.knots.len() - 1]
+    }
+    // Move one step, updating every knot even when the ones ahead stood still
+    fn move_one_step_updating_all_knots(&mut self, d: Direction) -> Position {
+        self.move_head(d);
+        for i in 1..self.knots.len() {
+            self.knots[i] = self.follow(self.knots[i - 1], self.knots[i]);
         }
-        self.links[self.links.len() - 1].tail
+        self.knots[self.knots.len() - 1]
     }
 }
 
@@ -90,6 +134,25 @@ enum Direction {
     Down,
     Left,
     Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    fn offset(self) -> Position {
+        match self {
+            Self::Up => (0, 1),
+            Self::Down => (0, -1),
+            Self::Right => (1, 0),
+            Self::Left => (-1, 0),
+            Self::UpLeft => (-1, 1),
+            Self::UpRight => (1, 1),
+            Self::DownLeft => (-1, -1),
+            Self::DownRight => (1, -1),
+        }
+    }
 }
 
 impl FromStr for Direction {
@@ -100,6 +163,10 @@ impl FromStr for Direction {
             "D" => Self::Down,
             "L" => Self::Left,
             "R" => Self::Right,
+            "UL" => Self::UpLeft,
+            "UR" => Self::UpRight,
+            "DL" => Self::DownLeft,
+            "DR" => Self::DownRight,
             _ => Result::Err(format!("Invalid direction: {s}"))?,
         })
     }
@@ -127,6 +194,21 @@ impl FromStr for Move {
 #[derive(Debug, Default, Clone)]
 struct Moves(VecDeque<Move>);
 
+impl Moves {
+    // The smallest and largest coordinates visited by the head. Every other knot follows it, so
+    // stays within the same bounds.
+    fn bounding_box(&self) -> (Position, Position) {
+        let (mut min, mut max, mut head) = ((0, 0), (0, 0), (0, 0));
+        for m in &self.0 {
+            let offset = m.dir.offset();
+            head = (head.0 + offset.0 * m.num, head.1 + offset.1 * m.num);
+            min = (min.0.min(head.0), min.1.min(head.1));
+            max = (max.0.max(head.0), max.1.max(head.1));
+        }
+        (min, max)
+    }
+}
+
 impl FromStr for Moves {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -134,36 +216,44 @@ impl FromStr for Moves {
     }
 }
 
-#[derive(Default)]
-struct Simulation<N: ArrayLength<RopeLink>> {
-    rope: Rope<N>,
+struct Simulation<'a> {
+    rope: Rope<'a>,
     remaining_moves: Moves,
+    update_all_knots: bool,
 }
 
-impl<N> Simulation<N>
-where
-    N: ArrayLength<RopeLink>,
-{
-    fn from_moves(moves: Moves) -> Self {
+impl<'a> Simulation<'a> {
+    fn from_moves(moves: Moves, num_knots: usize) -> Self {
+        Self::with_rule(moves, num_knots, &PUZZLE_RULE)
+    }
+    fn with_rule(moves: Moves, num_knots: usize, rule: &'a dyn FollowRule) -> Self {
         Self {
+            rope: Rope::new(num_knots, rule),
             remaining_moves: moves,
-            ..Default::default()
+            update_all_knots: false,
+        }
+    }
+    fn updating_all_knots(moves: Moves, num_knots: usize) -> Self {
+        Self {
+            update_all_knots: true,
+            ..Self::from_moves(moves, num_knots)
         }
     }
 }
 
-impl<N> Iterator for Simulation<N>
-where
-    N: ArrayLength<RopeLink>,
-{
+impl Iterator for Simulation<'_> {
     // the tail positon
     type Item = Position;
     fn next(&mut self) -> Option<Self::Item> {
         if self.remaining_moves.0.is_empty() {
             None
         } else {
-            let mut next_move = self.remaining_moves.0.get_mut(0).unwrap();
-            let tail = Some(self.rope.move_one_step(next_move.dir));
+            let next_move = self.remaining_moves.0.get_mut(0).unwrap();
+            let tail = Some(if self.update_all_knots {
+                self.rope.move_one_step_updating_all_knots(next_move.dir)
+            } else {
+                self.rope.move_one_step(next_move.dir)
+            });
             if next_move.num == 1 {
                 self.remaining_moves.0.pop_front();
             } else {
@@ -174,6 +264,134 @@ where
     }
 }
 
+// The character drawn for a knot, as in the puzzle for the first ten
+fn knot_char(index: usize) -> char {
+    match index {
+        0 => 'H',
+        1..=9 => char::from_digit(index as u32, 10).unwrap(),
+        // Longer ropes continue with letters, then all share the last one
+        _ => char::from(b'a' + (index - 10).min(25) as u8),
+    }
+}
+
+// Render the rows and columns around the head, with the knots over the positions visited by the
+// tail ('#') and the starting position ('s')
+fn render_window(
+    rope: &Rope,
+    visited: &HashSet<Position>,
+    num_rows: usize,
+    num_cols: usize,
+) -> String {
+    let head = rope.knots[0];
+    let top = head.1 + num_rows as i32 / 2;
+    let left = head.0 - num_cols as i32 / 2;
+    (0..num_rows as i32)
+        .map(|row| {
+            (0..num_cols as i32)
+                .map(|col| {
+                    let position = (left + col, top - row);
+                    // Knots closer to the head are drawn over the ones behind them
+                    if let Some(index) = rope.knots.iter().position(|knot| *knot == position) {
+                        knot_char(index)
+                    } else if position == (0, 0) {
+                        's'
+                    } else if visited.contains(&position) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .chain(once('\n'))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+pub struct Animation {
+    pub num_knots: usize,
+    pub follow_rule: Box<dyn FollowRule>,
+    pub max_fps: u32,
+    pub viewport_rows: usize,
+    pub viewport_cols: usize,
+}
+
+impl Animation {
+    // Redraw the rope around its head after every step.
+    // Returns the number of positions visited by the tail, as in the puzzle.
+    pub fn run(&self, data: &str) -> Result<usize, String> {
+        let moves: Moves = data.parse()?;
+        let mut simulation =
+            Simulation::with_rule(moves, self.num_knots.max(1), &*self.follow_rule);
+        let mut frame_limiter = FrameLimiter::new(self.max_fps);
+        let mut visited = HashSet::new();
+        while let Some(tail) = simulation.next() {
+            visited.insert(tail);
+            frame_limiter.draw_frame(&format!(
+                "{}head at {:?}, tail visited {} positions",
+                render_window(
+                    &simulation.rope,
+                    &visited,
+                    self.viewport_rows,
+                    self.viewport_cols
+                ),
+                simulation.rope.knots[0],
+                visited.len()
+            ))?;
+        }
+        Ok(visited.len())
+    }
+}
+
+// From black for unvisited cells, through red, to yellow for the most visited ones
+fn heat_colour(visits: usize, max_visits: usize) -> Rgb {
+    if visits == 0 {
+        return [0, 0, 0];
+    }
+    // Logarithmic, so that cells visited once remain visible next to the busiest ones
+    let heat = ((visits as f64).ln_1p() / (max_visits as f64).ln_1p()).clamp(0., 1.);
+    [
+        (80. + 175. * (2. * heat).min(1.)) as u8,
+        (255. * (2. * heat - 1.).max(0.)) as u8,
+        0,
+    ]
+}
+
+// Write an image of how many times the tail visited each cell, north up
+pub fn write_tail_heat_map(
+    data: &str,
+    num_knots: usize,
+    follow_rule: &dyn FollowRule,
+    path: &Path,
+) -> Result<(), String> {
+    let (width, height, pixels) = tail_heat_map(data, num_knots, follow_rule)?;
+    write_ppm(path, width, height, &pixels)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+// The width, height and pixels of the tail's heat map, north up
+fn tail_heat_map(
+    data: &str,
+    num_knots: usize,
+    follow_rule: &dyn FollowRule,
+) -> Result<(usize, usize, Vec<Rgb>), String> {
+    let moves: Moves = data.parse()?;
+    let mut visits = HashMap::new();
+    for tail in Simulation::with_rule(moves, num_knots.max(1), follow_rule) {
+        *visits.entry(tail).or_insert(0) += 1;
+    }
+    let min_x = visits.keys().map(|p| p.0).min().unwrap_or(0);
+    let max_x = visits.keys().map(|p| p.0).max().unwrap_or(0);
+    let min_y = visits.keys().map(|p| p.1).min().unwrap_or(0);
+    let max_y = visits.keys().map(|p| p.1).max().unwrap_or(0);
+    let (width, height) = ((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize);
+    let max_visits = visits.values().copied().max().unwrap_or(0);
+    let pixels = (0..height as i32)
+        .flat_map(|row| (0..width as i32).map(move |col| (min_x + col, max_y - row)))
+        .map(|position| heat_colour(*visits.get(&position).unwrap_or(&0), max_visits))
+        .collect::<Vec<_>>();
+    Ok((width, height, pixels))
+}
+
 type Input = Moves;
 type Parsed = Moves;
 type Output = usize;
@@ -183,23 +401,64 @@ fn parse_input(data: &str) -> Parsed {
     data.parse().unwrap()
 }
 
-#[aoc(day9, part1)]
-fn part1(data: &Input) -> Output {
-    Simulation::<U1>::from_moves(data.clone())
+fn num_tail_positions(moves: &Moves, num_knots: usize) -> Output {
+    num_tail_positions_with_rule(moves, num_knots, &PUZZLE_RULE)
+}
+
+fn num_tail_positions_with_rule(
+    moves: &Moves,
+    num_knots: usize,
+    follow_rule: &dyn FollowRule,
+) -> Output {
+    let (min, max) = moves.bounding_box();
+    let width = (max.0 - min.0 + 1) as usize;
+    let height = (max.1 - min.1 + 1) as usize;
+    let mut visited = BitSet::with_capacity(width * height);
+    for tail in Simulation::with_rule(moves.clone(), num_knots, follow_rule) {
+        visited.insert((tail.1 - min.1) as usize * width + (tail.0 - min.0) as usize);
+    }
+    visited.len()
+}
+
+// Update every knot and collect tail positions in a BTreeSet, for the BTreeSet variants
+fn num_tail_positions_in_btree_set(moves: &Moves, num_knots: usize) -> Output {
+    Simulation::updating_all_knots(moves.clone(), num_knots)
         .collect::<BTreeSet<_>>()
         .len()
 }
 
+#[aoc(day9, part1)]
+fn part1(data: &Input) -> Output {
+    num_tail_positions(data, 2)
+}
+
+#[aoc(day9, part1, BTreeSet)]
+fn part1_btree_set(data: &Input) -> Output {
+    num_tail_positions_in_btree_set(data, 2)
+}
+
 #[aoc(day9, part2)]
 fn part2(data: &Input) -> Output {
-    Simulation::<U9>::from_moves(data.clone())
-        .collect::<BTreeSet<_>>()
-        .len()
+    num_tail_positions(data, 10)
+}
+
+#[aoc(day9, part2, BTreeSet)]
+fn part2_btree_set(data: &Input) -> Output {
+    num_tail_positions_in_btree_set(data, 10)
+}
+
+// Not the puzzle's question: the number of positions visited by the tail of a rope with 50 knots
+#[aoc(day9, part2, FiftyKnots)]
+fn part2_fifty_knots(data: &Input) -> Output {
+    num_tail_positions(data, 50)
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate test;
     use super::*;
+    use crate::random::XorShift;
+    use test::Bencher;
 
     const EXAMPLE_SOLUTION_PART1: Output = 13;
     const SOLUTION_PART1: Output = 6081;
@@ -231,4 +490,153 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input()), SOLUTION_PART2)
     }
+    #[test]
+    fn test_part1_btree_set() {
+        assert_eq!(part1_btree_set(&input()), SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part2_btree_set() {
+        assert_eq!(part2_btree_set(&input()), SOLUTION_PART2)
+    }
+    // Long moves in random directions, drifting away from the origin
+    fn generated_moves(num_moves: usize, seed: u64) -> Moves {
+        let mut rng = XorShift::new(seed);
+        Moves(
+            (0..num_moves)
+                .map(|_| Move {
+                    dir: match rng.below(9) {
+                        0 | 1 => Direction::Up,
+                        2 | 3 => Direction::Down,
+                        4 | 5 => Direction::Left,
+                        _ => Direction::Right,
+                    },
+                    num: 1 + rng.below(30) as i32,
+                })
+                .collect(),
+        )
+    }
+    #[test]
+    fn test_bounding_box() {
+        assert_eq!(example_input().bounding_box(), ((0, 0), (5, 4)));
+        assert_eq!(example_input_part2().bounding_box(), ((-11, -5), (14, 15)));
+    }
+    #[test]
+    fn test_agreement_on_generated_moves() {
+        for seed in 1..10 {
+            let moves = generated_moves(500, seed);
+            for num_knots in [1, 2, 10, 30] {
+                assert_eq!(
+                    num_tail_positions(&moves, num_knots),
+                    num_tail_positions_in_btree_set(&moves, num_knots),
+                    "seed {seed}, {num_knots} knots"
+                );
+            }
+        }
+    }
+    #[bench]
+    fn bench_part2_on_generated_moves(b: &mut Bencher) {
+        let moves = generated_moves(10_000, 1);
+        b.iter(|| part2(&moves))
+    }
+    #[bench]
+    fn bench_part2_btree_set_on_generated_moves(b: &mut Bencher) {
+        let moves = generated_moves(10_000, 1);
+        b.iter(|| part2_btree_set(&moves))
+    }
+    #[test]
+    fn test_diagonal_moves() {
+        let moves: Moves = "UR 3\nDR 2\nL 1\nDL 4".parse().unwrap();
+        assert_eq!(moves.bounding_box(), ((0, -3), (5, 3)));
+        let mut simulation = Simulation::from_moves(moves, 2);
+        let tails = simulation.by_ref().collect::<Vec<_>>();
+        // The tail follows diagonally, one step behind
+        assert_eq!(
+            tails,
+            [
+                (0, 0),
+                (1, 1),
+                (2, 2),
+                (3, 2),
+                (4, 1),
+                (4, 1),
+                (4, 1),
+                (3, 0),
+                (2, -1),
+                (1, -2)
+            ]
+        );
+        assert_eq!(simulation.rope.knots[0], (0, -3));
+        assert_eq!(
+            "UL 1\nDD 2".parse::<Moves>().unwrap_err(),
+            "Invalid direction: DD"
+        );
+    }
+    #[test]
+    fn test_follow_rules() {
+        assert_eq!(PUZZLE_RULE.follow((2, 1), (0, 0)), (1, 1));
+        assert_eq!(Chebyshev { slack: 2 }.follow((2, 2), (0, 0)), (0, 0));
+        assert_eq!(Chebyshev { slack: 2 }.follow((3, 1), (0, 0)), (1, 1));
+        // Diagonally adjacent knots are 2 steps apart
+        assert_eq!(Manhattan { slack: 1 }.follow((1, 1), (0, 0)), (1, 0));
+        assert_eq!(Manhattan { slack: 2 }.follow((1, 1), (0, 0)), (0, 0));
+        assert_eq!(Manhattan { slack: 2 }.follow((3, 1), (0, 0)), (2, 0));
+        // A negative slack can never be satisfied, so the knot stops on top of the one before
+        assert_eq!(Chebyshev { slack: -1 }.follow((3, 1), (0, 0)), (3, 1));
+        assert_eq!(Manhattan { slack: -1 }.follow((3, 1), (0, 0)), (3, 1));
+        // The puzzle's rule matches its answers
+        let moves = input();
+        assert_eq!(
+            num_tail_positions_with_rule(&moves, 10, &Chebyshev { slack: 1 }),
+            SOLUTION_PART2
+        );
+        // More slack lets the tail cut corners, so it covers fewer cells
+        let coverage = |rule: &dyn FollowRule| num_tail_positions_with_rule(&moves, 10, rule);
+        assert!(coverage(&Chebyshev { slack: 2 }) < coverage(&Chebyshev { slack: 1 }));
+        assert!(coverage(&Manhattan { slack: 2 }) < coverage(&Manhattan { slack: 1 }));
+        // Manhattan distances are larger, so the same slack gives less leeway
+        assert!(coverage(&Manhattan { slack: 2 }) > coverage(&Chebyshev { slack: 2 }));
+    }
+    #[test]
+    fn test_render_window() {
+        // As in the puzzle's first example for part 2
+        let mut simulation = Simulation::from_moves("R 4\nU 4".parse().unwrap(), 10);
+        let mut visited = simulation.by_ref().take(4).collect::<HashSet<_>>();
+        assert_eq!(
+            render_window(&simulation.rope, &visited, 3, 11),
+            "...........\n.4321H.....\n...........\n"
+        );
+        visited.extend(simulation.by_ref());
+        assert_eq!(visited, HashSet::from([(0, 0)]));
+        assert_eq!(
+            render_window(&simulation.rope, &visited, 9, 9),
+            ".........\n.........\n.........\n.........\n\
+             ....H....\n....1....\n..432....\n.5.......\n6........\n"
+        );
+    }
+    #[test]
+    fn test_tail_heat_map() {
+        let (width, height, pixels) =
+            tail_heat_map(include_str!("../example/2022/day9.txt"), 2, &PUZZLE_RULE).unwrap();
+        // The tail visits from (0, 0) to (4, 4) in the example
+        assert_eq!((width, height), (5, 5));
+        // 13 cells visited, the others stay black
+        assert_eq!(
+            pixels.iter().filter(|pixel| **pixel != [0, 0, 0]).count(),
+            13
+        );
+    }
+    #[test]
+    fn test_rope_lengths() {
+        // A single knot is its own tail, and visits every position the head moves to
+        assert_eq!(num_tail_positions(&"R 4\nU 2\nL 4".parse().unwrap(), 1), 10);
+        // Every knot follows the previous one with a delay, so longer ropes never cover more
+        let moves = input();
+        let coverage = (1..=50)
+            .map(|num_knots| num_tail_positions(&moves, num_knots))
+            .collect::<Vec<_>>();
+        assert!(coverage.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert_eq!(coverage[1], SOLUTION_PART1);
+        assert_eq!(coverage[9], SOLUTION_PART2);
+        assert_eq!(coverage[49], part2_fifty_knots(&moves));
+    }
 }
@@ -1,78 +1,313 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
-use crate::input_parser::parse_line_vec;
+use crate::image_writer::{scale_pixels, write_pbm};
+use drawille::Canvas;
 use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::string::ToString;
 
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct Registers {
+    x: isize,
+    y: isize,
+}
+
+impl Display for Registers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "x={} y={}", self.x, self.y)
+    }
+}
+
+struct InstructionDefinition {
+    mnemonic: &'static str,
+    num_operands: usize,
+    // Number of cycles the instruction takes to complete. Its effect applies at the end of the
+    // last one
+    cycles: usize,
+    effect: fn(&mut Registers, &[isize]),
+}
+
+const INSTRUCTION_SET: [InstructionDefinition; 5] = [
+    InstructionDefinition {
+        mnemonic: "noop",
+        num_operands: 0,
+        cycles: 1,
+        effect: |_, _| {},
+    },
+    InstructionDefinition {
+        mnemonic: "addx",
+        num_operands: 1,
+        cycles: 2,
+        effect: |registers, operands| registers.x += operands[0],
+    },
+    InstructionDefinition {
+        mnemonic: "addy",
+        num_operands: 1,
+        cycles: 2,
+        effect: |registers, operands| registers.y += operands[0],
+    },
+    InstructionDefinition {
+        mnemonic: "setx",
+        num_operands: 1,
+        cycles: 1,
+        effect: |registers, operands| registers.x = operands[0],
+    },
+    InstructionDefinition {
+        mnemonic: "swap",
+        num_operands: 0,
+        cycles: 1,
+        effect: |registers, _| std::mem::swap(&mut registers.x, &mut registers.y),
+    },
+];
+
 #[derive(Clone)]
-enum Operation {
-    Noop,
-    Addx(isize),
+struct Instruction {
+    definition: &'static InstructionDefinition,
+    operands: Vec<isize>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum InstructionError {
+    Empty,
+    UnknownMnemonic(String),
+    MissingOperands {
+        mnemonic: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    TrailingTokens {
+        mnemonic: &'static str,
+        tokens: String,
+    },
+    InvalidOperand {
+        operand: String,
+        error: ParseIntError,
+    },
+}
+
+impl Display for InstructionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Empty instruction"),
+            Self::UnknownMnemonic(mnemonic) => write!(f, "Unknown instruction '{mnemonic}'"),
+            Self::MissingOperands {
+                mnemonic,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'{mnemonic}' expects {expected} operand(s), found {found}"
+            ),
+            Self::TrailingTokens { mnemonic, tokens } => {
+                write!(
+                    f,
+                    "Unexpected '{tokens}' after '{mnemonic}' and its operands"
+                )
+            }
+            Self::InvalidOperand { operand, error } => {
+                write!(f, "Invalid operand '{operand}': {error}")
+            }
+        }
+    }
 }
 
-impl FromStr for Operation {
-    type Err = String;
+#[derive(Debug, Eq, PartialEq)]
+struct ParseError {
+    // Starting from 1
+    line: usize,
+    error: InstructionError,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.error)
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = InstructionError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "noop" => Ok(Self::Noop),
-            s => Ok(Self::Addx(
-                s.split_once(' ')
-                    .ok_or_else(|| "Expected a line with a single space".to_string())?
-                    .1
+        let mut tokens = s.split_whitespace();
+        let mnemonic = tokens.next().ok_or(InstructionError::Empty)?;
+        let definition = INSTRUCTION_SET
+            .iter()
+            .find(|definition| definition.mnemonic == mnemonic)
+            .ok_or_else(|| InstructionError::UnknownMnemonic(mnemonic.to_string()))?;
+        let operands = tokens
+            .by_ref()
+            .take(definition.num_operands)
+            .map(|operand| {
+                operand
                     .parse()
-                    .map_err(|e| format!("{e}"))?,
-            )),
+                    .map_err(|error| InstructionError::InvalidOperand {
+                        operand: operand.to_string(),
+                        error,
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if operands.len() != definition.num_operands {
+            return Err(InstructionError::MissingOperands {
+                mnemonic: definition.mnemonic,
+                expected: definition.num_operands,
+                found: operands.len(),
+            });
         }
+        let trailing_tokens = tokens.collect::<Vec<_>>();
+        if !trailing_tokens.is_empty() {
+            return Err(InstructionError::TrailingTokens {
+                mnemonic: definition.mnemonic,
+                tokens: trailing_tokens.join(" "),
+            });
+        }
+        Ok(Self {
+            definition,
+            operands,
+        })
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.definition.mnemonic)?;
+        for operand in &self.operands {
+            write!(f, " {operand}")?;
+        }
+        Ok(())
+    }
+}
+
+// What happened during one cycle of the CPU
+struct CycleTrace {
+    // Starting from 1
+    cycle: usize,
+    instruction: Instruction,
+    // The registers during the cycle
+    before: Registers,
+    // The registers once the cycle is over
+    after: Registers,
+}
+
+impl Display for CycleTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>4}: {:<10} {} -> {}",
+            self.cycle,
+            self.instruction.to_string(),
+            self.before,
+            self.after
+        )
     }
 }
 
 #[derive(Clone)]
 struct Computer {
-    register_value: isize,
-    operations: VecDeque<Operation>,
-    operation_in_progress: Option<Operation>,
+    registers: Registers,
+    cycle: usize,
+    instructions: VecDeque<Instruction>,
+    // The instruction being executed and the number of cycles it has left
+    instruction_in_progress: Option<(Instruction, usize)>,
 }
 
-impl Iterator for Computer {
-    type Item = isize;
-    fn next(&mut self) -> Option<Self::Item> {
-        let previous_register_value = self.register_value;
-        if let Some(operation) = &self.operation_in_progress {
-            match operation {
-                Operation::Addx(x) => {
-                    self.register_value += x;
-                    self.operation_in_progress = None;
-                    return Some(previous_register_value);
-                }
-                _ => {
-                    panic!("Only Addx can be in progress");
-                }
+impl Computer {
+    fn step(&mut self) -> Option<CycleTrace> {
+        let (instruction, cycles_left) = match self.instruction_in_progress.take() {
+            Some(in_progress) => in_progress,
+            None => {
+                let instruction = self.instructions.pop_front()?;
+                let cycles = instruction.definition.cycles;
+                (instruction, cycles)
             }
+        };
+        self.cycle += 1;
+        let before = self.registers;
+        if cycles_left == 1 {
+            (instruction.definition.effect)(&mut self.registers, &instruction.operands);
+        } else {
+            self.instruction_in_progress = Some((instruction.clone(), cycles_left - 1));
         }
-        self.operations.pop_front().map(|next_operation| {
-            match next_operation {
-                Operation::Addx(_) => {
-                    self.operation_in_progress = Some(next_operation);
-                }
-                Operation::Noop => { /* Do nothing, as instructed */ }
-            }
-            previous_register_value
+        Some(CycleTrace {
+            cycle: self.cycle,
+            instruction,
+            before,
+            after: self.registers,
         })
     }
+    fn trace(mut self) -> impl Iterator<Item = CycleTrace> {
+        std::iter::from_fn(move || self.step())
+    }
+}
+
+// Yields the value of the x register during each cycle
+impl Iterator for Computer {
+    type Item = isize;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.step().map(|trace| trace.before.x)
+    }
 }
 
 impl FromStr for Computer {
-    type Err = String;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Self {
-            register_value: 1,
-            operations: parse_line_vec(s)?.into_iter().collect(),
-            operation_in_progress: None,
+            registers: Registers { x: 1, y: 0 },
+            cycle: 0,
+            instructions: s
+                .lines()
+                .enumerate()
+                .map(|(index, line)| {
+                    line.parse().map_err(|error| ParseError {
+                        line: index + 1,
+                        error,
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            instruction_in_progress: None,
         })
     }
 }
 
+// One line per cycle, with the instruction being executed and the registers before and after it
+pub fn trace(data: &str) -> Result<String, String> {
+    let computer: Computer = data.parse().map_err(|e: ParseError| e.to_string())?;
+    Ok(computer
+        .trace()
+        .map(|cycle| cycle.to_string())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+pub enum CrtOutput {
+    // Print '#' for lit pixels and '.' for dark ones
+    Text,
+    // Print each pixel as a braille dot
+    Braille,
+    // Write a black and white image, with each pixel scaled to a square of scale x scale pixels
+    Pbm { path: PathBuf, scale: usize },
+}
+
+// Render the picture drawn on the CRT
+pub fn render_crt(
+    data: &str,
+    geometry: &MonitorGeometry,
+    output: &CrtOutput,
+) -> Result<(), String> {
+    geometry.validate()?;
+    let computer = data.parse().map_err(|e: ParseError| e.to_string())?;
+    let monitor = render_with_geometry(&computer, geometry);
+    match output {
+        CrtOutput::Text => println!("{}", monitor.to_string()),
+        CrtOutput::Braille => print!("{}", monitor.to_canvas().frame()),
+        CrtOutput::Pbm { path, scale } => monitor
+            .write_pbm(path, (*scale).max(1))
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))?,
+    }
+    Ok(())
+}
+
 type Parsed = Computer;
 type Input = Computer;
 type OutputPart1 = isize;
@@ -83,38 +318,158 @@ fn parse_input(data: &str) -> Parsed {
     data.parse().unwrap()
 }
 
-#[aoc(day10, part1)]
-fn part1(data: &Input) -> OutputPart1 {
-    data.clone()
+// The cycles during which the signal strength is sampled
+struct SampleSchedule {
+    first: usize,
+    period: usize,
+    num_samples: usize,
+}
+
+impl Default for SampleSchedule {
+    fn default() -> Self {
+        Self {
+            first: 20,
+            period: 40,
+            num_samples: 6,
+        }
+    }
+}
+
+impl SampleSchedule {
+    fn contains(&self, cycle: usize) -> bool {
+        cycle.checked_sub(self.first).is_some_and(|offset| {
+            offset.is_multiple_of(self.period) && offset / self.period < self.num_samples
+        })
+    }
+}
+
+fn sum_of_signal_strengths(computer: &Computer, schedule: &SampleSchedule) -> isize {
+    computer
+        .clone()
         .enumerate()
         .map(|(index, register_value)| (index + 1, register_value))
-        .filter(move |(index, _register_value)| {
-            *index == 20 || *index > 20 && (*index - 20) % 40 == 0
-        })
-        .take(6)
-        .map(|(index, register_value)| index as isize * register_value)
+        .filter(|(cycle, _register_value)| schedule.contains(*cycle))
+        .map(|(cycle, register_value)| cycle as isize * register_value)
         .sum()
 }
 
-const DISPLAY_WIDTH: usize = 40;
-const DISPLAY_HEIGHT: usize = 6;
+#[aoc(day10, part1)]
+fn part1(data: &Input) -> OutputPart1 {
+    sum_of_signal_strengths(data, &SampleSchedule::default())
+}
+
+pub struct MonitorGeometry {
+    pub width: usize,
+    pub height: usize,
+    // Number of pixels covered by the sprite, centred on the x register
+    pub sprite_width: usize,
+}
+
+impl Default for MonitorGeometry {
+    fn default() -> Self {
+        Self {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+        }
+    }
+}
+
+impl MonitorGeometry {
+    fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("width", self.width),
+            ("height", self.height),
+            ("sprite width", self.sprite_width),
+        ] {
+            if value == 0 {
+                return Err(format!("The monitor's {name} must be at least 1"));
+            }
+        }
+        Ok(())
+    }
+}
 
 struct Monitor {
-    pixels: [bool; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+    width: usize,
+    pixels: Vec<bool>,
 }
 
 impl Monitor {
-    fn new() -> Self {
+    fn new(geometry: &MonitorGeometry) -> Self {
         Self {
-            pixels: [false; DISPLAY_WIDTH * DISPLAY_HEIGHT],
+            width: geometry.width,
+            pixels: vec![false; geometry.width * geometry.height],
         }
     }
+    fn height(&self) -> usize {
+        self.pixels.len() / self.width
+    }
+}
+
+// The pixels of each letter of the font used by the CRT, as far as they're known from puzzle outputs
+const FONT: [(char, [&str; GLYPH_HEIGHT]); 16] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 6;
+// Glyphs are separated by a column of blank pixels
+const GLYPH_SPACING: usize = 1;
+
+impl Monitor {
+    // Read the letters displayed on the monitor
+    fn letters(&self) -> Result<String, String> {
+        if self.height() != GLYPH_HEIGHT {
+            return Err(format!(
+                "Letters are {GLYPH_HEIGHT} pixels high, the monitor is {}",
+                self.height()
+            ));
+        }
+        if self.width < GLYPH_WIDTH {
+            return Err(format!(
+                "Letters are {GLYPH_WIDTH} pixels wide, the monitor is {}",
+                self.width
+            ));
+        }
+        let art = self.to_string();
+        let lines = art.lines().collect::<Vec<_>>();
+        (0..self.width - GLYPH_WIDTH + 1)
+            .step_by(GLYPH_WIDTH + GLYPH_SPACING)
+            .map(|col| {
+                let glyph = lines
+                    .iter()
+                    .map(|line| &line[col..col + GLYPH_WIDTH])
+                    .collect::<Vec<_>>();
+                FONT.iter()
+                    .find(|(_, pixels)| pixels[..] == glyph[..])
+                    .map(|(letter, _)| *letter)
+                    .ok_or_else(|| {
+                        format!("Unrecognised glyph at column {col}:\n{}", glyph.join("\n"))
+                    })
+            })
+            .collect()
+    }
 }
 
 impl ToString for Monitor {
     fn to_string(&self) -> String {
         self.pixels
-            .chunks(DISPLAY_WIDTH)
+            .chunks(self.width)
             .map(|line| {
                 line.iter()
                     .map(|b| if *b { '#' } else { '.' })
@@ -125,20 +480,55 @@ impl ToString for Monitor {
     }
 }
 
-#[aoc(day10, part2)]
-fn part2(data: &Input) -> OutputPart2 {
-    let mut monitor = Monitor::new();
+impl Monitor {
+    fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(self.width as u32, self.height() as u32);
+        for (position, _) in self.pixels.iter().enumerate().filter(|(_, lit)| **lit) {
+            canvas.set(
+                (position % self.width) as u32,
+                (position / self.width) as u32,
+            );
+        }
+        canvas
+    }
+    fn write_pbm(&self, path: &Path, scale: usize) -> std::io::Result<()> {
+        let height = self.height();
+        let pixels = scale_pixels(self.width, height, scale, &self.pixels);
+        write_pbm(path, self.width * scale, height * scale, &pixels)
+    }
+}
+
+fn render_with_geometry(data: &Input, geometry: &MonitorGeometry) -> Monitor {
+    let mut monitor = Monitor::new(geometry);
+    let num_pixels = monitor.pixels.len();
+    // The leftmost pixel covered by the sprite, relative to the x register
+    let sprite_offset = (geometry.sprite_width as isize - 1) / 2;
     data.clone()
         .enumerate()
-        .take(DISPLAY_WIDTH * DISPLAY_HEIGHT)
+        .take(num_pixels)
         .for_each(|(pixel_position, sprite_position)| {
-            if ((sprite_position - 1)..=(sprite_position + 1))
-                .contains(&((pixel_position % DISPLAY_WIDTH) as isize))
+            let sprite_start = sprite_position - sprite_offset;
+            if (sprite_start..sprite_start + geometry.sprite_width as isize)
+                .contains(&((pixel_position % geometry.width) as isize))
             {
                 monitor.pixels[pixel_position] = true;
             }
         });
-    monitor.to_string()
+    monitor
+}
+
+fn render(data: &Input) -> Monitor {
+    render_with_geometry(data, &MonitorGeometry::default())
+}
+
+#[aoc(day10, part2)]
+fn part2(data: &Input) -> OutputPart2 {
+    render(data).letters().unwrap()
+}
+
+#[aoc(day10, part2, Art)]
+fn part2_art(data: &Input) -> OutputPart2 {
+    render(data).to_string()
 }
 
 #[cfg(test)]
@@ -147,13 +537,14 @@ mod tests {
 
     const EXAMPLE_SOLUTION_PART1: OutputPart1 = 13140;
     const SOLUTION_PART1: OutputPart1 = 17840;
-    const EXAMPLE_SOLUTION_PART2: &str = "##..##..##..##..##..##..##..##..##..##..
+    const EXAMPLE_SOLUTION_PART2_ART: &str = "##..##..##..##..##..##..##..##..##..##..
 ###...###...###...###...###...###...###.
 ####....####....####....####....####....
 #####.....#####.....#####.....#####.....
 ######......######......######......####
 #######.......#######.......#######.....";
-    const SOLUTION_PART2: &str = "####..##..#.....##..#..#.#....###...##..
+    const SOLUTION_PART2: &str = "EALGULPG";
+    const SOLUTION_PART2_ART: &str = "####..##..#.....##..#..#.#....###...##..
 #....#..#.#....#..#.#..#.#....#..#.#..#.
 ###..#..#.#....#....#..#.#....#..#.#....
 #....####.#....#.##.#..#.#....###..#.##.
@@ -175,11 +566,156 @@ mod tests {
         assert_eq!(part1(&input()), SOLUTION_PART1)
     }
     #[test]
-    fn test_part2_given_example_input() {
-        assert_eq!(part2(&example_input()).as_str(), EXAMPLE_SOLUTION_PART2)
+    fn test_part2_art_given_example_input() {
+        assert_eq!(
+            part2_art(&example_input()).as_str(),
+            EXAMPLE_SOLUTION_PART2_ART
+        )
+    }
+    #[test]
+    fn test_part2_art() {
+        assert_eq!(part2_art(&input()), SOLUTION_PART2_ART)
     }
     #[test]
     fn test_part2() {
         assert_eq!(part2(&input()), SOLUTION_PART2)
     }
+    #[test]
+    fn test_trace() {
+        let program = "noop\naddx 3\naddy -5\nsetx 7\nswap\nnoop";
+        assert_eq!(
+            trace(program).unwrap(),
+            "   1: noop       x=1 y=0 -> x=1 y=0
+   2: addx 3     x=1 y=0 -> x=1 y=0
+   3: addx 3     x=1 y=0 -> x=4 y=0
+   4: addy -5    x=4 y=0 -> x=4 y=0
+   5: addy -5    x=4 y=0 -> x=4 y=-5
+   6: setx 7     x=4 y=-5 -> x=7 y=-5
+   7: swap       x=7 y=-5 -> x=-5 y=7
+   8: noop       x=-5 y=7 -> x=-5 y=7"
+        );
+    }
+    #[test]
+    fn test_sample_schedule() {
+        let every_other_cycle = SampleSchedule {
+            first: 1,
+            period: 2,
+            num_samples: 3,
+        };
+        // x is 1, 1, 1, 4 then 4 during the first cycles
+        assert_eq!(
+            sum_of_signal_strengths(
+                &"noop\naddx 3\naddx -5".parse().unwrap(),
+                &every_other_cycle
+            ),
+            1 + 3 + 5 * 4
+        );
+    }
+    #[test]
+    fn test_monitor_geometry() {
+        let narrow = MonitorGeometry {
+            width: 20,
+            height: 12,
+            sprite_width: 3,
+        };
+        // The first cycles draw the same pixels, but the rest is shifted relative to the sprite
+        let wide_art = part2_art(&input());
+        let narrow_art = render_with_geometry(&input(), &narrow).to_string();
+        assert_eq!(narrow_art.lines().count(), 12);
+        assert_eq!(narrow_art[..20], wide_art[..20]);
+        assert!(render_with_geometry(&input(), &narrow)
+            .letters()
+            .unwrap_err()
+            .starts_with("Letters are 6 pixels high"));
+        let too_narrow = MonitorGeometry {
+            width: 3,
+            height: 6,
+            sprite_width: 3,
+        };
+        assert_eq!(
+            render_with_geometry(&input(), &too_narrow).letters(),
+            Err("Letters are 4 pixels wide, the monitor is 3".to_string())
+        );
+        for geometry in [
+            MonitorGeometry {
+                width: 0,
+                ..MonitorGeometry::default()
+            },
+            MonitorGeometry {
+                height: 0,
+                ..MonitorGeometry::default()
+            },
+            MonitorGeometry {
+                sprite_width: 0,
+                ..MonitorGeometry::default()
+            },
+        ] {
+            assert!(render_crt(
+                include_str!("../input/2022/day10.txt"),
+                &geometry,
+                &CrtOutput::Text
+            )
+            .unwrap_err()
+            .ends_with("must be at least 1"));
+        }
+        let wide_sprite = MonitorGeometry {
+            width: 8,
+            height: 1,
+            sprite_width: 4,
+        };
+        // The sprite covers from x - 1 to x + 2
+        assert_eq!(
+            render_with_geometry(
+                &"addx 5\nnoop\nnoop\nnoop\nnoop\nnoop\nnoop"
+                    .parse()
+                    .unwrap(),
+                &wide_sprite
+            )
+            .to_string(),
+            "##...###"
+        );
+    }
+    #[test]
+    fn test_malformed_instructions() {
+        let error = |s: &str| s.parse::<Computer>().err().map(|e| e.to_string());
+        assert_eq!(
+            error("noop\nmulx 3"),
+            Some("Line 2: Unknown instruction 'mulx'".to_string())
+        );
+        assert_eq!(
+            error("noop\n\nnoop"),
+            Some("Line 2: Empty instruction".to_string())
+        );
+        assert_eq!(
+            error("addx"),
+            Some("Line 1: 'addx' expects 1 operand(s), found 0".to_string())
+        );
+        assert_eq!(
+            error("addx 1\naddx 2 3"),
+            Some("Line 2: Unexpected '3' after 'addx' and its operands".to_string())
+        );
+        assert_eq!(
+            error("noop\nnoop 1 2"),
+            Some("Line 2: Unexpected '1 2' after 'noop' and its operands".to_string())
+        );
+        assert_eq!(
+            error("addx three"),
+            Some("Line 1: Invalid operand 'three': invalid digit found in string".to_string())
+        );
+        assert!(matches!(
+            "swap\nADDX 1".parse::<Computer>(),
+            Err(ParseError {
+                line: 2,
+                error: InstructionError::UnknownMnemonic(mnemonic)
+            }) if mnemonic == "ADDX"
+        ));
+    }
+    #[test]
+    fn test_unrecognised_glyph() {
+        // The example doesn't display letters
+        assert_eq!(
+            render(&example_input()).letters(),
+            Err("Unrecognised glyph at column 0:\n##..\n###.\n####\n####\n####\n####".to_string())
+        );
+    }
 }
@@ -1,32 +1,367 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
-use crate::input_parser::parse_delimited_vec;
+use crate::input_parser::{parse_delimited_vec, ByteCursor};
 use scan_rules::scan;
-use std::collections::{BinaryHeap, VecDeque};
+use serde_derive::Serialize;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::iter::once;
+use std::rc::Rc;
 use std::str::FromStr;
 
-#[derive(Clone)]
-enum Operation {
-    Add(u64),
-    Multiply(u64),
-    Square,
+#[derive(Clone, Copy)]
+enum OverflowProtection {
+    Divide(u64),
+    Modulus(u64),
+}
+
+// A monkey's worry level for an item, with arithmetic that reports overflow instead of wrapping
+trait WorryLevel: Clone + Display {
+    // Build a worry level, given the divisors each monkey tests items with
+    fn new(value: u64, divisors: &[u64]) -> Self;
+    // A constant of the same kind as self
+    fn constant(&self, value: u64) -> Self;
+    fn add(&self, other: &Self) -> Result<Self, String>;
+    fn sub(&self, other: &Self) -> Result<Self, String>;
+    // Subtract, knowing that only the result modulo `modulus` matters, so it never underflows
+    fn sub_modulo(&self, other: &Self, modulus: u64) -> Result<Self, String>;
+    fn mul(&self, other: &Self) -> Result<Self, String>;
+    fn div(&self, other: &Self) -> Result<Self, String>;
+    fn rem(&self, other: &Self) -> Result<Self, String>;
+    fn relieve(&self, protection: OverflowProtection) -> Result<Self, String>;
+    fn is_divisible_by(&self, divisor: u64) -> bool;
 }
 
-impl Default for Operation {
-    fn default() -> Self {
-        Self::Add(0)
+macro_rules! impl_worry_level_for_unsigned {
+    ($t: ty) => {
+        impl WorryLevel for $t {
+            fn new(value: u64, _divisors: &[u64]) -> Self {
+                value.into()
+            }
+            fn constant(&self, value: u64) -> Self {
+                value.into()
+            }
+            fn add(&self, other: &Self) -> Result<Self, String> {
+                self.checked_add(*other)
+                    .ok_or_else(|| format!("{self} + {other} overflows {}", stringify!($t)))
+            }
+            fn sub(&self, other: &Self) -> Result<Self, String> {
+                self.checked_sub(*other)
+                    .ok_or_else(|| format!("{self} - {other} underflows {}", stringify!($t)))
+            }
+            fn sub_modulo(&self, other: &Self, modulus: u64) -> Result<Self, String> {
+                let modulus = <$t>::from(modulus);
+                let other = other
+                    .checked_rem(modulus)
+                    .ok_or_else(|| format!("{other} modulo 0 divides by zero"))?;
+                self.add(&(modulus - other))
+            }
+            fn mul(&self, other: &Self) -> Result<Self, String> {
+                self.checked_mul(*other)
+                    .ok_or_else(|| format!("{self} * {other} overflows {}", stringify!($t)))
+            }
+            fn div(&self, other: &Self) -> Result<Self, String> {
+                self.checked_div(*other)
+                    .ok_or_else(|| format!("{self} / {other} divides by zero"))
+            }
+            fn rem(&self, other: &Self) -> Result<Self, String> {
+                self.checked_rem(*other)
+                    .ok_or_else(|| format!("{self} % {other} divides by zero"))
+            }
+            fn relieve(&self, protection: OverflowProtection) -> Result<Self, String> {
+                match protection {
+                    OverflowProtection::Divide(x) => self.checked_div(x.into()),
+                    OverflowProtection::Modulus(x) => self.checked_rem(x.into()),
+                }
+                .ok_or_else(|| "Relief by zero".to_string())
+            }
+            fn is_divisible_by(&self, divisor: u64) -> bool {
+                self % <$t>::from(divisor) == 0
+            }
+        }
+    };
+}
+
+impl_worry_level_for_unsigned!(u64);
+impl_worry_level_for_unsigned!(u128);
+
+// A worry level only known modulo each monkey's divisor: enough to decide where items are thrown,
+// and it never overflows, however many rounds are played
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct Residues {
+    divisors: Rc<[u64]>,
+    // residues[i] is the worry level modulo divisors[i]
+    residues: Vec<u64>,
+}
+
+impl Residues {
+    fn zip_with(&self, other: &Self, f: impl Fn(u128, u128) -> u128) -> Self {
+        self.zip_with_divisor(other, |a, b, _| f(a, b))
+    }
+    fn zip_with_divisor(&self, other: &Self, f: impl Fn(u128, u128, u128) -> u128) -> Self {
+        Self {
+            divisors: self.divisors.clone(),
+            residues: self
+                .divisors
+                .iter()
+                .zip(self.residues.iter().zip(other.residues.iter()))
+                .map(|(d, (a, b))| {
+                    let d = *d as u128;
+                    (f(*a as u128, *b as u128, d) % d) as u64
+                })
+                .collect(),
+        }
     }
 }
 
-impl FromStr for Operation {
+impl Display for Residues {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let residues = self
+            .residues
+            .iter()
+            .zip(self.divisors.iter())
+            .map(|(r, d)| format!("{r} mod {d}"))
+            .collect::<Vec<_>>();
+        write!(f, "[{}]", residues.join(", "))
+    }
+}
+
+impl WorryLevel for Residues {
+    fn new(value: u64, divisors: &[u64]) -> Self {
+        Self {
+            divisors: divisors.into(),
+            residues: divisors.iter().map(|d| value % d).collect(),
+        }
+    }
+    fn constant(&self, value: u64) -> Self {
+        Self {
+            divisors: self.divisors.clone(),
+            residues: self.divisors.iter().map(|d| value % d).collect(),
+        }
+    }
+    fn add(&self, other: &Self) -> Result<Self, String> {
+        Ok(self.zip_with(other, |a, b| a + b))
+    }
+    fn sub(&self, other: &Self) -> Result<Self, String> {
+        Ok(self.zip_with_divisor(other, |a, b, d| a + d - b))
+    }
+    fn sub_modulo(&self, other: &Self, _modulus: u64) -> Result<Self, String> {
+        self.sub(other)
+    }
+    fn mul(&self, other: &Self) -> Result<Self, String> {
+        Ok(self.zip_with(other, |a, b| a * b))
+    }
+    fn div(&self, _other: &Self) -> Result<Self, String> {
+        Err("Can't divide a worry level only known by its residues".to_string())
+    }
+    fn rem(&self, _other: &Self) -> Result<Self, String> {
+        Err("Can't take the remainder of a worry level only known by its residues".to_string())
+    }
+    fn relieve(&self, protection: OverflowProtection) -> Result<Self, String> {
+        match protection {
+            // Residues modulo each divisor are kept as is by a modulus all divisors divide
+            OverflowProtection::Modulus(x) if self.divisors.iter().all(|d| x % d == 0) => {
+                Ok(self.clone())
+            }
+            OverflowProtection::Modulus(x) => Err(format!(
+                "Modulus {x} isn't a multiple of every divisor: {:?}",
+                self.divisors
+            )),
+            OverflowProtection::Divide(x) => Err(format!(
+                "Can't divide by {x} a worry level only known by its residues"
+            )),
+        }
+    }
+    fn is_divisible_by(&self, divisor: u64) -> bool {
+        self.divisors
+            .iter()
+            .zip(self.residues.iter())
+            .any(|(d, r)| *d == divisor && *r == 0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl Operator {
+    fn symbol(self) -> char {
+        match self {
+            Self::Add => '+',
+            Self::Subtract => '-',
+            Self::Multiply => '*',
+            Self::Divide => '/',
+            Self::Remainder => '%',
+        }
+    }
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Add | Self::Subtract => 0,
+            Self::Multiply | Self::Divide | Self::Remainder => 1,
+        }
+    }
+    fn apply<W: WorryLevel>(self, lhs: &W, rhs: &W, modulus: Option<u64>) -> Result<W, String> {
+        match self {
+            Self::Add => lhs.add(rhs),
+            Self::Subtract => match modulus {
+                Some(modulus) => lhs.sub_modulo(rhs, modulus),
+                None => lhs.sub(rhs),
+            },
+            Self::Multiply => lhs.mul(rhs),
+            Self::Divide => lhs.div(rhs),
+            Self::Remainder => lhs.rem(rhs),
+        }
+    }
+}
+
+// The right hand side of a monkey's "new = ..." operation
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+enum Expression {
+    #[default]
+    Old,
+    Literal(u64),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+impl Expression {
+    // Division and remainder give different results once worry levels are reduced modulo
+    // the product of divisors
+    fn divides(&self) -> bool {
+        match self {
+            Self::Old | Self::Literal(_) => false,
+            Self::Binary(lhs, operator, rhs) => {
+                matches!(operator, Operator::Divide | Operator::Remainder)
+                    || lhs.divides()
+                    || rhs.divides()
+            }
+        }
+    }
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Old | Self::Literal(_) => u8::MAX,
+            Self::Binary(_, operator, _) => operator.precedence(),
+        }
+    }
+    fn apply<W: WorryLevel>(&self, old: &W) -> Result<W, String> {
+        self.evaluate(old, None)
+    }
+    // Only the result modulo `modulus` matters: intermediate results may then be offset by
+    // multiples of it, so that subtractions don't underflow
+    fn apply_modulo<W: WorryLevel>(&self, old: &W, modulus: u64) -> Result<W, String> {
+        self.evaluate(old, Some(modulus))
+    }
+    fn evaluate<W: WorryLevel>(&self, old: &W, modulus: Option<u64>) -> Result<W, String> {
+        match self {
+            Self::Old => Ok(old.clone()),
+            Self::Literal(x) => Ok(old.constant(*x)),
+            Self::Binary(lhs, operator, rhs) => operator.apply(
+                &lhs.evaluate(old, modulus)?,
+                &rhs.evaluate(old, modulus)?,
+                modulus,
+            ),
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Old => write!(f, "old"),
+            Self::Literal(x) => write!(f, "{x}"),
+            Self::Binary(lhs, operator, rhs) => {
+                // Operators are left associative, so only the right hand side needs parentheses
+                // at equal precedence
+                if lhs.precedence() < operator.precedence() {
+                    write!(f, "({lhs})")?;
+                } else {
+                    write!(f, "{lhs}")?;
+                }
+                write!(f, " {} ", operator.symbol())?;
+                if rhs.precedence() <= operator.precedence() {
+                    write!(f, "({rhs})")
+                } else {
+                    write!(f, "{rhs}")
+                }
+            }
+        }
+    }
+}
+
+struct ExpressionParser<'a> {
+    cursor: ByteCursor<'a>,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            cursor: ByteCursor::new(s),
+        }
+    }
+    fn parse_operator(&mut self, operators: &[Operator]) -> Option<Operator> {
+        let next = self.cursor.peek()?;
+        let operator = *operators.iter().find(|op| op.symbol() as u8 == next)?;
+        self.cursor.advance(1);
+        Some(operator)
+    }
+    // factor := "old" | integer | "(" sum ")"
+    fn parse_factor(&mut self) -> Result<Expression, String> {
+        match self.cursor.peek() {
+            Some(b'(') => {
+                self.cursor.advance(1);
+                let expression = self.parse_sum()?;
+                if self.cursor.peek() != Some(b')') {
+                    return Err(self.cursor.error("')'"));
+                }
+                self.cursor.advance(1);
+                Ok(expression)
+            }
+            Some(b) if b.is_ascii_digit() => self.cursor.parse_unsigned().map(Expression::Literal),
+            _ if self.cursor.remaining().starts_with(b"old") => {
+                self.cursor.advance(3);
+                Ok(Expression::Old)
+            }
+            _ => Err(self.cursor.error("'old', an integer or '('")),
+        }
+    }
+    // product := factor (("*" | "/" | "%") factor)*
+    fn parse_product(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_factor()?;
+        while let Some(operator) =
+            self.parse_operator(&[Operator::Multiply, Operator::Divide, Operator::Remainder])
+        {
+            let rhs = self.parse_factor()?;
+            expression = Expression::Binary(Box::new(expression), operator, Box::new(rhs));
+        }
+        Ok(expression)
+    }
+    // sum := product (("+" | "-") product)*
+    fn parse_sum(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_product()?;
+        while let Some(operator) = self.parse_operator(&[Operator::Add, Operator::Subtract]) {
+            let rhs = self.parse_product()?;
+            expression = Expression::Binary(Box::new(expression), operator, Box::new(rhs));
+        }
+        Ok(expression)
+    }
+    fn parse_expression(mut self) -> Result<Expression, String> {
+        let expression = self.parse_sum()?;
+        match self.cursor.peek() {
+            None => Ok(expression),
+            Some(_) => Err(self.cursor.error("an operator")),
+        }
+    }
+}
+
+impl FromStr for Expression {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        scan!(s;
-            ("new = old * old") =>  Self::Square ,
-            ("new = old + ", let x: u64) =>  Self::Add(x as u64),
-            ("new = old * ", let x: u64) => Self::Multiply(x as u64),
-        )
-        .map_err(|e| format!("{e}"))
+        ExpressionParser::new(s).parse_expression()
     }
 }
 
@@ -50,23 +385,17 @@ impl FromStr for Items {
     }
 }
 
-#[derive(Clone, Copy)]
-enum OverflowProtection {
-    Divide(u64),
-    Modulus(u64),
-}
-
 #[derive(Default, Clone)]
-struct Monkey {
+struct Monkey<W> {
     index: usize,
-    items: VecDeque<u64>,
-    operation: Operation,
+    items: VecDeque<W>,
+    operation: Expression,
     test_divisibility: u64,
     destination: Destination,
     num_items_inspected: usize,
 }
 
-impl FromStr for Monkey {
+impl FromStr for Monkey<u64> {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let lines = s.lines();
@@ -75,7 +404,7 @@ impl FromStr for Monkey {
             scan!(line;
                 ("Monkey ", let index: usize, .._) => {monkey.index = index},
                 ("  Starting items: ", ..items) => {monkey.items = items.parse::<Items>()?.0},
-                ("  Operation: ", ..operation) => {monkey.operation = operation.parse()?},
+                ("  Operation: new = ", ..operation) => {monkey.operation = operation.parse()?},
                 ("  Test: divisible by ", let test_divisibility: u64) => {monkey.test_divisibility = test_divisibility as u64},
                 ("    If true: throw to monkey ", let i: usize) => {monkey.destination.if_true = i},
                 ("    If false: throw to monkey ", let i: usize) => { monkey.destination.if_false = i },
@@ -86,63 +415,169 @@ impl FromStr for Monkey {
     }
 }
 
-impl Monkey {
-    fn throw(&mut self, protection: OverflowProtection) -> Option<(u64, usize)> {
-        let item = self.items.pop_front();
-        item.map(|item| {
-            let mut new_item = match self.operation {
-                Operation::Add(x) => item + x,
-                Operation::Multiply(x) => item * x,
-                Operation::Square => item * item,
-            };
-            new_item = match protection {
-                OverflowProtection::Divide(x) => new_item / x,
-                OverflowProtection::Modulus(x) => new_item % x,
-            };
-            let dest = match new_item % self.test_divisibility {
-                0 => self.destination.if_true,
-                _ => self.destination.if_false,
-            };
-            self.num_items_inspected += 1;
-            (new_item, dest)
-        })
+impl<W: WorryLevel> Monkey<W> {
+    // The new worry level of an item this monkey inspects, and who it's thrown to
+    fn inspect(&self, item: &W, protection: OverflowProtection) -> Result<(W, usize), String> {
+        if let OverflowProtection::Modulus(modulus) = protection {
+            if self.operation.divides() {
+                return Err(format!(
+                    "Monkey {}: new = {} can't be computed modulo {modulus}",
+                    self.index, self.operation
+                ));
+            }
+        }
+        let new_item = match protection {
+            OverflowProtection::Divide(_) => self.operation.apply(item),
+            OverflowProtection::Modulus(modulus) => self.operation.apply_modulo(item, modulus),
+        }
+        .and_then(|new_item| new_item.relieve(protection))
+        .map_err(|e| {
+            format!(
+                "Monkey {} inspecting an item with worry level {item}: {e}",
+                self.index
+            )
+        })?;
+        let dest = if new_item.is_divisible_by(self.test_divisibility) {
+            self.destination.if_true
+        } else {
+            self.destination.if_false
+        };
+        Ok((new_item, dest))
     }
-    fn catch(&mut self, item: u64) {
+    fn catch(&mut self, item: W) {
         self.items.push_back(item)
     }
 }
 
 #[derive(Clone)]
-struct Monkeys {
-    monkeys: Vec<Monkey>,
+struct Monkeys<W> {
+    monkeys: Vec<Monkey<W>>,
 }
 
-impl FromStr for Monkeys {
+impl FromStr for Monkeys<u64> {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Monkeys {
             monkeys: s
                 .split("\n\n")
                 .map(|m| m.parse())
-                .collect::<Result<Vec<Monkey>, _>>()?,
+                .collect::<Result<Vec<Monkey<u64>>, _>>()?,
         })
     }
 }
 
-impl Monkeys {
-    fn play_one_round(&mut self, protection: OverflowProtection) {
+impl Monkeys<u64> {
+    // The same monkeys, tracking worry levels with another type
+    fn with_worry_levels<W: WorryLevel>(&self) -> Monkeys<W> {
+        let divisors = self
+            .monkeys
+            .iter()
+            .map(|monkey| monkey.test_divisibility)
+            .collect::<Vec<_>>();
+        Monkeys {
+            monkeys: self
+                .monkeys
+                .iter()
+                .map(|monkey| Monkey {
+                    index: monkey.index,
+                    items: monkey
+                        .items
+                        .iter()
+                        .map(|item| W::new(*item, &divisors))
+                        .collect(),
+                    operation: monkey.operation.clone(),
+                    test_divisibility: monkey.test_divisibility,
+                    destination: monkey.destination.clone(),
+                    num_items_inspected: monkey.num_items_inspected,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<W: WorryLevel> Monkeys<W> {
+    fn play_one_round(&mut self, protection: OverflowProtection) -> Result<(), String> {
+        self.play_one_round_with(protection, |_, _| {})
+    }
+    // Play one round, calling on_throw(origin, dest) for each item thrown
+    fn play_one_round_with(
+        &mut self,
+        protection: OverflowProtection,
+        mut on_throw: impl FnMut(usize, usize),
+    ) -> Result<(), String> {
+        for origin in 0..self.monkeys.len() {
+            // Take the items out of the monkey, so it can inspect them while others catch them
+            let mut items = std::mem::take(&mut self.monkeys[origin].items);
+            for item in items.drain(..) {
+                let (item, dest) = self.monkeys[origin].inspect(&item, protection)?;
+                self.monkeys[origin].num_items_inspected += 1;
+                on_throw(origin, dest);
+                self.monkeys[dest].catch(item);
+            }
+            // Give the emptied queue back to reuse its allocation, along with any item the
+            // monkey threw to itself
+            items.append(&mut self.monkeys[origin].items);
+            self.monkeys[origin].items = items;
+        }
+        Ok(())
+    }
+    // Clone each monkey to satisfy the borrow checker, rather than draining its items.
+    // Slower, and exposed as the Cloning variant of part 2.
+    fn play_one_round_by_cloning(&mut self, protection: OverflowProtection) -> Result<(), String> {
         for origin in 0..self.monkeys.len() {
             let mut tmp_monkey = self.monkeys[origin].clone();
-            while let Some((item, dest)) = tmp_monkey.throw(protection) {
+            while let Some(item) = tmp_monkey.items.pop_front() {
+                let (item, dest) = tmp_monkey.inspect(&item, protection)?;
+                tmp_monkey.num_items_inspected += 1;
                 self.monkeys[dest].catch(item);
             }
             self.monkeys[origin] = tmp_monkey;
         }
+        Ok(())
     }
-    fn play_n_rounds(&mut self, n: usize, protection: OverflowProtection) -> Output {
+    fn play_one_round_with_snapshot(
+        &mut self,
+        round: usize,
+        protection: OverflowProtection,
+    ) -> Result<RoundSnapshot<W>, String> {
+        let num_monkeys = self.monkeys.len();
+        let mut throws = vec![vec![0; num_monkeys]; num_monkeys];
+        self.play_one_round_with(protection, |origin, dest| throws[origin][dest] += 1)?;
+        Ok(RoundSnapshot {
+            round,
+            items: self
+                .monkeys
+                .iter()
+                .map(|monkey| monkey.items.iter().cloned().collect())
+                .collect(),
+            inspections: self
+                .monkeys
+                .iter()
+                .map(|monkey| monkey.num_items_inspected)
+                .collect(),
+            throws,
+        })
+    }
+    // Play n rounds, yielding what happened after each of them
+    fn snapshots(
+        &mut self,
+        n: usize,
+        protection: OverflowProtection,
+    ) -> impl Iterator<Item = Result<RoundSnapshot<W>, String>> + '_ {
+        (1..=n).map(move |round| self.play_one_round_with_snapshot(round, protection))
+    }
+    fn play_n_rounds(
+        &mut self,
+        n: usize,
+        protection: OverflowProtection,
+    ) -> Result<Output, String> {
         for _ in 0..n {
-            self.play_one_round(protection);
+            self.play_one_round(protection)?;
         }
+        Ok(self.monkey_business())
+    }
+    // The product of the number of items inspected by the two most active monkeys
+    fn monkey_business(&self) -> Output {
         let mut top_inspectors = self
             .monkeys
             .iter()
@@ -153,8 +588,198 @@ impl Monkeys {
     }
 }
 
-type Parsed = Monkeys;
-type Input = Monkeys;
+// The state of the monkeys at the end of a round
+#[derive(Serialize)]
+struct RoundSnapshot<W> {
+    round: usize,
+    // The worry level of each item held by each monkey
+    items: Vec<Vec<W>>,
+    // The number of items each monkey inspected since the start of the game
+    inspections: Vec<usize>,
+    // throws[origin][dest] is the number of items origin threw to dest during this round
+    throws: Vec<Vec<usize>>,
+}
+
+pub enum StatisticsFormat {
+    // One row per round, with the number of items held and inspected by each monkey, and the
+    // number of items thrown between each pair of monkeys that can throw to each other
+    Csv,
+    // An array with one object per round, including the worry level of each item
+    Json,
+}
+
+pub struct Statistics {
+    // Divide worry levels by 3 after each inspection like in part 1, rather than keeping them
+    // modulo the product of the monkeys' divisors like in part 2
+    pub with_relief: bool,
+    pub rounds: usize,
+    pub format: StatisticsFormat,
+}
+
+impl Statistics {
+    pub fn write(&self, data: &str, writer: &mut impl Write) -> Result<(), String> {
+        let mut monkeys: Monkeys<u64> = data.parse()?;
+        let protection = if self.with_relief {
+            OverflowProtection::Divide(3)
+        } else {
+            OverflowProtection::Modulus(product_of_divisors(&monkeys))
+        };
+        // Only report throws that can happen, to keep the columns readable
+        let throw_columns = monkeys
+            .monkeys
+            .iter()
+            .enumerate()
+            .flat_map(|(origin, monkey)| {
+                [
+                    (origin, monkey.destination.if_true),
+                    (origin, monkey.destination.if_false),
+                ]
+            })
+            .collect::<BTreeSet<_>>();
+        let num_monkeys = monkeys.monkeys.len();
+        let snapshots = monkeys.snapshots(self.rounds, protection);
+        match self.format {
+            StatisticsFormat::Csv => {
+                let header = once("round".to_string())
+                    .chain((0..num_monkeys).flat_map(|index| {
+                        [
+                            format!("monkey_{index}_items"),
+                            format!("monkey_{index}_inspections"),
+                        ]
+                    }))
+                    .chain(
+                        throw_columns
+                            .iter()
+                            .map(|(origin, dest)| format!("monkey_{origin}_to_{dest}")),
+                    )
+                    .collect::<Vec<_>>();
+                writeln!(writer, "{}", header.join(",")).map_err(|e| format!("{e}"))?;
+                for snapshot in snapshots {
+                    let snapshot = snapshot?;
+                    let row = once(snapshot.round)
+                        .chain((0..num_monkeys).flat_map(|index| {
+                            [snapshot.items[index].len(), snapshot.inspections[index]]
+                        }))
+                        .chain(
+                            throw_columns
+                                .iter()
+                                .map(|(origin, dest)| snapshot.throws[*origin][*dest]),
+                        )
+                        .map(|value| value.to_string())
+                        .collect::<Vec<_>>();
+                    writeln!(writer, "{}", row.join(",")).map_err(|e| format!("{e}"))?;
+                }
+            }
+            StatisticsFormat::Json => {
+                write!(writer, "[").map_err(|e| format!("{e}"))?;
+                for (index, snapshot) in snapshots.enumerate() {
+                    let separator = if index == 0 { "\n" } else { ",\n" };
+                    write!(writer, "{separator}").map_err(|e| format!("{e}"))?;
+                    serde_json::to_writer(&mut *writer, &snapshot?).map_err(|e| format!("{e}"))?;
+                }
+                writeln!(writer, "\n]").map_err(|e| format!("{e}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The inspections of a single item, round after round, until its (monkey, worry level) state at
+// the start of a round repeats
+struct ItemHistory {
+    // inspections[r][m] is how many times monkey m inspected the item during its first r rounds
+    inspections: Vec<Vec<u64>>,
+    // The round from which the item's history repeats, until the last round in inspections
+    cycle_start: usize,
+}
+
+impl ItemHistory {
+    fn inspections_after(&self, rounds: u64) -> Vec<u64> {
+        let recorded = self.inspections.len() as u64 - 1;
+        if rounds <= recorded {
+            return self.inspections[rounds as usize].clone();
+        }
+        let cycle_start = self.cycle_start as u64;
+        let period = recorded - cycle_start;
+        let (num_cycles, remainder) = (
+            (rounds - cycle_start) / period,
+            (rounds - cycle_start) % period,
+        );
+        let (start, end) = (
+            &self.inspections[self.cycle_start],
+            &self.inspections[recorded as usize],
+        );
+        self.inspections[(cycle_start + remainder) as usize]
+            .iter()
+            .zip(start.iter().zip(end.iter()))
+            .map(|(partial, (start, end))| partial + num_cycles * (end - start))
+            .collect()
+    }
+}
+
+impl Monkeys<u64> {
+    // Play one round from the point of view of a single item: it is inspected by each monkey it's
+    // thrown to, until it lands with a monkey that has already played this round
+    fn play_one_round_for_item(
+        &self,
+        (mut monkey, mut worry): (usize, u64),
+        protection: OverflowProtection,
+        inspections: &mut [u64],
+    ) -> Result<(usize, u64), String> {
+        loop {
+            inspections[monkey] += 1;
+            let (new_worry, dest) = self.monkeys[monkey].inspect(&worry, protection)?;
+            worry = new_worry;
+            if dest <= monkey {
+                return Ok((dest, worry));
+            }
+            monkey = dest;
+        }
+    }
+    fn item_history(&self, mut state: (usize, u64), modulus: u64) -> Result<ItemHistory, String> {
+        let protection = OverflowProtection::Modulus(modulus);
+        let mut first_seen = HashMap::new();
+        let mut inspections = vec![vec![0; self.monkeys.len()]];
+        loop {
+            if let Some(cycle_start) = first_seen.insert(state, inspections.len() - 1) {
+                return Ok(ItemHistory {
+                    inspections,
+                    cycle_start,
+                });
+            }
+            let mut next = inspections.last().unwrap().clone();
+            state = self.play_one_round_for_item(state, protection, &mut next)?;
+            inspections.push(next);
+        }
+    }
+    // Same as play_n_rounds with OverflowProtection::Modulus(modulus), but tracks each item on its
+    // own and extrapolates its inspections as soon as its trajectory repeats, so the cost doesn't
+    // depend on the number of rounds
+    fn play_n_rounds_by_cycles(&self, n: u64, modulus: u64) -> Result<u128, String> {
+        let mut inspections = vec![0; self.monkeys.len()];
+        for (index, monkey) in self.monkeys.iter().enumerate() {
+            for item in &monkey.items {
+                let history = self.item_history((index, item % modulus), modulus)?;
+                for (total, count) in inspections.iter_mut().zip(history.inspections_after(n)) {
+                    *total += count;
+                }
+            }
+        }
+        let mut top_inspectors = inspections.into_iter().collect::<BinaryHeap<_>>();
+        let (alpha, beta) = (top_inspectors.pop().unwrap(), top_inspectors.pop().unwrap());
+        Ok(alpha as u128 * beta as u128)
+    }
+}
+
+fn product_of_divisors(data: &Input) -> u64 {
+    data.monkeys
+        .iter()
+        .map(|monkey| monkey.test_divisibility)
+        .product()
+}
+
+type Parsed = Monkeys<u64>;
+type Input = Monkeys<u64>;
 type Output = usize;
 
 #[aoc_generator(day11)]
@@ -166,22 +791,53 @@ fn parse_input(data: &str) -> Parsed {
 fn part1(data: &Input) -> Output {
     let mut data = data.clone();
     data.play_n_rounds(20, OverflowProtection::Divide(3))
+        .unwrap()
+}
+
+#[aoc(day11, part1, U128)]
+fn part1_u128(data: &Input) -> Output {
+    data.with_worry_levels::<u128>()
+        .play_n_rounds(20, OverflowProtection::Divide(3))
+        .unwrap()
 }
 
 #[aoc(day11, part2)]
 fn part2(data: &Input) -> Output {
     let mut data = data.clone();
-    let modulus = data
-        .monkeys
-        .iter()
-        .map(|monkey| monkey.test_divisibility)
-        .product();
+    let modulus = product_of_divisors(&data);
     data.play_n_rounds(10_000, OverflowProtection::Modulus(modulus))
+        .unwrap()
+}
+
+#[aoc(day11, part2, Cloning)]
+fn part2_cloning(data: &Input) -> Output {
+    let mut data = data.clone();
+    let protection = OverflowProtection::Modulus(product_of_divisors(&data));
+    for _ in 0..10_000 {
+        data.play_one_round_by_cloning(protection).unwrap();
+    }
+    data.monkey_business()
+}
+
+#[aoc(day11, part2, Cycles)]
+fn part2_cycles(data: &Input) -> Output {
+    data.play_n_rounds_by_cycles(10_000, product_of_divisors(data))
+        .unwrap() as Output
+}
+
+#[aoc(day11, part2, Residues)]
+fn part2_residues(data: &Input) -> Output {
+    let modulus = product_of_divisors(data);
+    data.with_worry_levels::<Residues>()
+        .play_n_rounds(10_000, OverflowProtection::Modulus(modulus))
+        .unwrap()
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate test;
     use super::*;
+    use test::Bencher;
 
     const EXAMPLE_SOLUTION_PART1: Output = 10605;
     const SOLUTION_PART1: Output = 55458;
@@ -210,4 +866,267 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input()), SOLUTION_PART2)
     }
+    #[test]
+    fn test_part1_u128_given_example_input() {
+        assert_eq!(part1_u128(&example_input()), EXAMPLE_SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part1_u128() {
+        assert_eq!(part1_u128(&input()), SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part2_residues_given_example_input() {
+        assert_eq!(part2_residues(&example_input()), EXAMPLE_SOLUTION_PART2)
+    }
+    #[test]
+    fn test_part2_residues() {
+        assert_eq!(part2_residues(&input()), SOLUTION_PART2)
+    }
+    #[test]
+    fn test_part2_cloning_given_example_input() {
+        assert_eq!(part2_cloning(&example_input()), EXAMPLE_SOLUTION_PART2)
+    }
+    #[test]
+    fn test_part2_cloning() {
+        assert_eq!(part2_cloning(&input()), SOLUTION_PART2)
+    }
+    #[bench]
+    fn bench_part2(b: &mut Bencher) {
+        let input = input();
+        b.iter(|| part2(&input))
+    }
+    #[bench]
+    fn bench_part2_cloning(b: &mut Bencher) {
+        let input = input();
+        b.iter(|| part2_cloning(&input))
+    }
+    #[test]
+    fn test_part2_cycles_given_example_input() {
+        assert_eq!(part2_cycles(&example_input()), EXAMPLE_SOLUTION_PART2)
+    }
+    #[test]
+    fn test_part2_cycles() {
+        assert_eq!(part2_cycles(&input()), SOLUTION_PART2)
+    }
+    #[test]
+    fn test_cycles_agree_with_simulation() {
+        for monkeys in [example_input(), input()] {
+            let modulus = product_of_divisors(&monkeys);
+            let mut simulated = monkeys.clone();
+            for rounds in 1..=300 {
+                let expected = simulated
+                    .play_n_rounds(1, OverflowProtection::Modulus(modulus))
+                    .unwrap();
+                assert_eq!(
+                    monkeys.play_n_rounds_by_cycles(rounds, modulus).unwrap(),
+                    expected as u128,
+                    "After {rounds} rounds"
+                );
+            }
+        }
+    }
+    #[test]
+    fn test_a_trillion_rounds() {
+        let monkeys = input();
+        let modulus = product_of_divisors(&monkeys);
+        let after_10_000 = monkeys.play_n_rounds_by_cycles(10_000, modulus).unwrap();
+        let after_a_trillion = monkeys
+            .play_n_rounds_by_cycles(1_000_000_000_000, modulus)
+            .unwrap();
+        // Inspection counts grow linearly with the number of rounds, so their product roughly
+        // grows with its square
+        let ratio = after_a_trillion / after_10_000;
+        assert!(
+            (9_000_000_000_000_000..11_000_000_000_000_000).contains(&ratio),
+            "{ratio}"
+        );
+    }
+    #[test]
+    fn test_parse_expressions() {
+        use Expression::*;
+        use Operator::*;
+        let binary = |lhs: Expression, operator, rhs: Expression| {
+            Binary(Box::new(lhs), operator, Box::new(rhs))
+        };
+        assert_eq!("old".parse(), Ok(Old));
+        assert_eq!("old * 19".parse(), Ok(binary(Old, Multiply, Literal(19))));
+        assert_eq!(
+            "old+1 - 2*old".parse(),
+            Ok(binary(
+                binary(Old, Add, Literal(1)),
+                Subtract,
+                binary(Literal(2), Multiply, Old)
+            ))
+        );
+        assert_eq!(
+            "(old + 3) % (7 / old)".parse(),
+            Ok(binary(
+                binary(Old, Add, Literal(3)),
+                Remainder,
+                binary(Literal(7), Divide, Old)
+            ))
+        );
+        for s in [
+            "old - (old - 1) * 2",
+            "(old + 1) * old % 5",
+            "old / 3 / (2 / old)",
+        ] {
+            assert_eq!(s.parse::<Expression>().unwrap().to_string(), s);
+        }
+    }
+    #[test]
+    fn test_malformed_expressions() {
+        assert_eq!(
+            "".parse::<Expression>(),
+            Err("Expected 'old', an integer or '(' at position 0, found end of input".to_string())
+        );
+        assert_eq!(
+            "old ^ 2".parse::<Expression>(),
+            Err("Expected an operator at position 4, found '^'".to_string())
+        );
+        assert_eq!(
+            "(old + 2".parse::<Expression>(),
+            Err("Expected ')' at position 8, found end of input".to_string())
+        );
+        assert_eq!(
+            "old * new".parse::<Expression>(),
+            Err("Expected 'old', an integer or '(' at position 6, found 'n'".to_string())
+        );
+    }
+    #[test]
+    fn test_evaluate_expressions() {
+        let evaluate = |s: &str, old: u64| s.parse::<Expression>().unwrap().apply(&old);
+        assert_eq!(evaluate("old * old - old / 2 + old % 3", 7), Ok(49 - 3 + 1));
+        assert_eq!(evaluate("(old + 1) * (old - 1)", 5), Ok(24));
+        assert_eq!(
+            evaluate("1 - old", 2),
+            Err("1 - 2 underflows u64".to_string())
+        );
+        assert_eq!(
+            evaluate("old % (old - 2)", 2),
+            Err("2 % 0 divides by zero".to_string())
+        );
+        let residues = Residues::new(10, &[3, 7]);
+        let expression = "old * old - 4 * old + 5".parse::<Expression>().unwrap();
+        assert_eq!(expression.apply(&residues), Ok(Residues::new(65, &[3, 7])));
+        // Modulo 21, 1 - 2 is 20
+        assert_eq!(
+            "1 - old"
+                .parse::<Expression>()
+                .unwrap()
+                .apply_modulo(&2u64, 21),
+            Ok(20)
+        );
+        assert!("old / 2"
+            .parse::<Expression>()
+            .unwrap()
+            .apply(&residues)
+            .is_err());
+    }
+    #[test]
+    fn test_richer_operations() {
+        // The example, with operations rewritten into equivalent but more elaborate expressions
+        let example = include_str!("../example/2022/day11.txt");
+        let with_ring_operations = example
+            .replace("new = old * 19", "new = (old * 20) - old")
+            .replace("new = old + 6", "new = 2 * (old + 3) - old");
+        let monkeys = parse_input(&with_ring_operations);
+        assert_eq!(part1(&monkeys), EXAMPLE_SOLUTION_PART1);
+        assert_eq!(part2_residues(&monkeys), EXAMPLE_SOLUTION_PART2);
+        // Worry levels reduced modulo the product of divisors may be smaller than what's subtracted
+        let with_subtraction = example.replace("new = old + 3", "new = old - 50000 + 50003");
+        let monkeys = parse_input(&with_subtraction);
+        assert_eq!(part2(&monkeys), part2_residues(&monkeys));
+        assert_eq!(part2_cycles(&monkeys), part2_residues(&monkeys));
+        assert_eq!(part2_cloning(&monkeys), part2_residues(&monkeys));
+        // Division and remainder don't commute with taking residues, so only work for part 1
+        let with_all_operations =
+            with_ring_operations.replace("new = old + 3", "new = (old + 9) / 3 * 3 - 6 + old % 3");
+        let monkeys = parse_input(&with_all_operations);
+        assert_eq!(part1(&monkeys), EXAMPLE_SOLUTION_PART1);
+        let modulus = product_of_divisors(&monkeys);
+        let expected_error =
+            "Monkey 3: new = (old + 9) / 3 * 3 - 6 + old % 3 can't be computed modulo 96577";
+        assert_eq!(
+            monkeys
+                .clone()
+                .play_n_rounds(10_000, OverflowProtection::Modulus(modulus))
+                .err(),
+            Some(expected_error.to_string())
+        );
+        assert_eq!(
+            monkeys.play_n_rounds_by_cycles(10_000, modulus).err(),
+            Some(expected_error.to_string())
+        );
+        assert!(monkeys
+            .with_worry_levels::<Residues>()
+            .play_n_rounds(10_000, OverflowProtection::Modulus(modulus))
+            .is_err());
+    }
+    #[test]
+    fn test_csv_statistics() {
+        let statistics = Statistics {
+            with_relief: true,
+            rounds: 20,
+            format: StatisticsFormat::Csv,
+        };
+        let mut csv = Vec::new();
+        statistics
+            .write(include_str!("../example/2022/day11.txt"), &mut csv)
+            .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 21);
+        assert_eq!(
+            lines[0],
+            "round,monkey_0_items,monkey_0_inspections,monkey_1_items,monkey_1_inspections,\
+             monkey_2_items,monkey_2_inspections,monkey_3_items,monkey_3_inspections,\
+             monkey_0_to_2,monkey_0_to_3,monkey_1_to_0,monkey_1_to_2,monkey_2_to_1,monkey_2_to_3,\
+             monkey_3_to_0,monkey_3_to_1"
+        );
+        // As in the puzzle's description of the first round
+        assert_eq!(lines[1], "1,4,2,6,4,0,3,0,5,0,2,4,0,1,2,0,5");
+        // And of the inspection counts after 20 rounds
+        assert!(lines[20].starts_with("20,5,101,5,95,0,7,0,105,"));
+    }
+    #[test]
+    fn test_json_statistics() {
+        let statistics = Statistics {
+            with_relief: true,
+            rounds: 2,
+            format: StatisticsFormat::Json,
+        };
+        let mut json = Vec::new();
+        statistics
+            .write(include_str!("../example/2022/day11.txt"), &mut json)
+            .unwrap();
+        let snapshots: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(snapshots.as_array().unwrap().len(), 2);
+        assert_eq!(
+            snapshots[0],
+            serde_json::json!({
+                "round": 1,
+                "items": [[20, 23, 27, 26], [2080, 25, 167, 207, 401, 1046], [], []],
+                "inspections": [2, 4, 3, 5],
+                "throws": [[0, 0, 0, 2], [4, 0, 0, 0], [0, 1, 0, 2], [0, 5, 0, 0]],
+            })
+        );
+        assert_eq!(
+            snapshots[1]["items"],
+            serde_json::json!([[695, 10, 71, 135, 350], [43, 49, 58, 55, 362], [], []])
+        );
+    }
+    #[test]
+    fn test_overflow_is_reported() {
+        // Without relief, squared worry levels overflow within a few rounds
+        let error = example_input()
+            .play_n_rounds(20, OverflowProtection::Divide(1))
+            .unwrap_err();
+        assert!(error.contains("overflows u64"), "{error}");
+        let error = example_input()
+            .with_worry_levels::<Residues>()
+            .play_n_rounds(20, OverflowProtection::Divide(3))
+            .unwrap_err();
+        assert!(error.starts_with("Monkey 0 inspecting"), "{error}");
+    }
 }
@@ -1,134 +1,291 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use bit_set::BitSet;
+use pathfinding::directed::astar::astar;
+use pathfinding::directed::dijkstra::dijkstra_all;
 use std::collections::VecDeque;
 use std::iter::once;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy)]
 struct Elevation(u8);
 
-impl From<char> for Elevation {
-    fn from(c: char) -> Self {
-        assert!(c == 'S' || c == 'E' || c.is_ascii_lowercase());
+impl TryFrom<char> for Elevation {
+    type Error = String;
+    fn try_from(c: char) -> Result<Self, Self::Error> {
         match c {
-            'S' => Self(0),
-            'E' => Self(25),
-            c => Self(c as u8 - b'a'),
+            'S' => Ok(Self(0)),
+            'E' => Ok(Self(25)),
+            'a'..='z' => Ok(Self(c as u8 - b'a')),
+            c => Err(format!("Invalid elevation: {c:?}")),
         }
     }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 struct Position {
-    row: u16,
-    col: u16,
+    row: usize,
+    col: usize,
 }
 
 impl Position {
-    fn from_index_and_num_cols(index: u16, num_cols: u16) -> Self {
+    fn from_index_and_num_cols(index: usize, num_cols: usize) -> Self {
         Self {
             row: index / num_cols,
             col: index % num_cols,
         }
     }
-    fn neighbours(&self, num_rows: u16, num_cols: u16) -> impl Iterator<Item = Position> + '_ {
+    fn neighbours(
+        self,
+        num_rows: usize,
+        num_cols: usize,
+        diagonal_moves: bool,
+    ) -> impl Iterator<Item = Position> {
         once((-1, 0))
             .chain(once((1, 0)))
             .chain(once((0, -1)))
             .chain(once((0, 1)))
-            .filter_map(|pos| {
-                (self.row as i16).checked_add(pos.0).and_then(|row| {
-                    (self.col as i16).checked_add(pos.1).map(|col| Self {
-                        row: row as u16,
-                        col: col as u16,
-                    })
+            .chain(
+                [(-1, -1), (-1, 1), (1, -1), (1, 1)]
+                    .into_iter()
+                    .filter(move |_| diagonal_moves),
+            )
+            .filter_map(move |(row_offset, col_offset)| {
+                Some(Self {
+                    row: self.row.checked_add_signed(row_offset)?,
+                    col: self.col.checked_add_signed(col_offset)?,
                 })
             })
             .filter(move |pos| pos.row < num_rows && pos.col < num_cols)
     }
-    fn to_index(self, num_cols: u16) -> usize {
-        (self.row * num_cols + self.col) as usize
+    // Can't overflow for positions within a parsed map: the index is below its number of cells,
+    // which are all in memory
+    fn to_index(self, num_cols: usize) -> usize {
+        self.row * num_cols + self.col
     }
 }
 
 #[derive(Debug, Clone)]
 struct Input {
-    num_cols: u16,
-    num_rows: u16,
+    num_cols: usize,
+    num_rows: usize,
     topology: Vec<Elevation>,
     start: Position,
     end: Position,
 }
 
+// Line and column, counting from 1, as shown by text editors
+fn location(pos: Position) -> String {
+    format!("line {}, column {}", pos.row + 1, pos.col + 1)
+}
+
+// Remember the position of a unique marker, or complain if it was already seen
+fn mark_unique(marker: &mut Option<Position>, pos: Position, name: &str) -> Result<(), String> {
+    match marker.replace(pos) {
+        Some(first) => Err(format!(
+            "Duplicate {name} at {}, already seen at {}",
+            location(pos),
+            location(first)
+        )),
+        None => Ok(()),
+    }
+}
+
 impl FromStr for Input {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let num_rows = s.lines().count() as u16;
+        let num_rows = s.lines().count();
         let num_cols = s
             .lines()
             .next()
             .ok_or_else(|| "No input line".to_string())?
             .chars()
-            .count() as u16;
-        let topology = s
-            .chars()
-            .filter(|c| *c != '\n')
-            .map(Elevation::from)
-            .collect();
-        let start = Position::from_index_and_num_cols(
-            s.chars()
-                .filter(|c| *c != '\n')
-                .position(|c| c == 'S')
-                .ok_or_else(|| "Missing start".to_string())? as u16,
-            num_cols,
-        );
-        let end = Position::from_index_and_num_cols(
-            s.chars()
-                .filter(|c| *c != '\n')
-                .position(|c| c == 'E')
-                .ok_or_else(|| "Missing end".to_string())? as u16,
-            num_cols,
-        );
+            .count();
+        // Every cell takes at least one byte, so this is enough even before rows are validated
+        let mut topology = Vec::with_capacity(s.len());
+        let (mut start, mut end) = (None, None);
+        for (row, line) in s.lines().enumerate() {
+            let width = line.chars().count();
+            if width != num_cols {
+                return Err(format!(
+                    "Line {} has {width} columns, expected {num_cols}",
+                    row + 1
+                ));
+            }
+            for (col, c) in line.chars().enumerate() {
+                let pos = Position { row, col };
+                match c {
+                    'S' => mark_unique(&mut start, pos, "start")?,
+                    'E' => mark_unique(&mut end, pos, "end")?,
+                    _ => {}
+                }
+                topology
+                    .push(Elevation::try_from(c).map_err(|e| format!("{e} at {}", location(pos)))?);
+            }
+        }
         Ok(Self {
             num_cols,
             num_rows,
             topology,
-            start,
-            end,
+            start: start.ok_or_else(|| "Missing start".to_string())?,
+            end: end.ok_or_else(|| "Missing end".to_string())?,
         })
     }
 }
 
+// Which steps are allowed, and what they cost
+#[derive(Debug, Clone, Copy)]
+pub struct MovementPolicy {
+    pub max_ascent: u8,
+    pub max_descent: u8,
+    pub diagonal_moves: bool,
+    // Each step costs `step_cost`, plus `ascent_cost` for each unit of elevation climbed and
+    // `descent_cost` for each unit descended
+    pub step_cost: u64,
+    pub ascent_cost: u64,
+    pub descent_cost: u64,
+}
+
+impl Default for MovementPolicy {
+    // The puzzle's rules: climb at most one higher, descend as far as needed, every step costs 1
+    fn default() -> Self {
+        Self {
+            max_ascent: 1,
+            max_descent: u8::MAX,
+            diagonal_moves: false,
+            step_cost: 1,
+            ascent_cost: 0,
+            descent_cost: 0,
+        }
+    }
+}
+
+impl MovementPolicy {
+    fn allows(&self, from: Elevation, to: Elevation) -> bool {
+        to.0 <= from.0.saturating_add(self.max_ascent)
+            && from.0 <= to.0.saturating_add(self.max_descent)
+    }
+    fn cost(&self, from: Elevation, to: Elevation) -> u64 {
+        self.step_cost
+            + self.ascent_cost * to.0.saturating_sub(from.0) as u64
+            + self.descent_cost * from.0.saturating_sub(to.0) as u64
+    }
+    // If all steps cost the same, a BFS finds the shortest paths. Otherwise, we need Dijkstra.
+    fn is_weighted(&self) -> bool {
+        self.ascent_cost != 0 || self.descent_cost != 0
+    }
+}
+
+impl Input {
+    fn elevation(&self, pos: Position) -> Elevation {
+        self.topology[pos.to_index(self.num_cols)]
+    }
+    fn neighbours(&self, pos: Position, policy: &MovementPolicy) -> impl Iterator<Item = Position> {
+        pos.neighbours(self.num_rows, self.num_cols, policy.diagonal_moves)
+    }
+    // Neighbours that can be reached in one step from pos, with the cost of that step
+    fn successors<'a>(
+        &'a self,
+        pos: Position,
+        policy: &'a MovementPolicy,
+    ) -> impl Iterator<Item = (Position, u64)> + 'a {
+        self.neighbours(pos, policy)
+            .filter(move |neighbour| policy.allows(self.elevation(pos), self.elevation(*neighbour)))
+            .map(move |neighbour| {
+                (
+                    neighbour,
+                    policy.cost(self.elevation(pos), self.elevation(neighbour)),
+                )
+            })
+    }
+    // Neighbours from which pos can be reached in one step, with the cost of that step
+    fn predecessors<'a>(
+        &'a self,
+        pos: Position,
+        policy: &'a MovementPolicy,
+    ) -> impl Iterator<Item = (Position, u64)> + 'a {
+        self.neighbours(pos, policy)
+            .filter(move |neighbour| policy.allows(self.elevation(*neighbour), self.elevation(pos)))
+            .map(move |neighbour| {
+                (
+                    neighbour,
+                    policy.cost(self.elevation(neighbour), self.elevation(pos)),
+                )
+            })
+    }
+}
+
 #[derive(Clone)]
 struct PathFinder {
     input: Input,
+    policy: MovementPolicy,
     cost_to_dest: Vec<Option<u64>>,
-    boundary: VecDeque<Position>,
+    // The neighbour to move to from each position to follow a shortest path to the destination
+    next_step: Vec<Option<Position>>,
 }
 
 impl PathFinder {
     fn from_input(input: Input) -> Self {
-        let end = input.end;
-        let boundary = end
-            .neighbours(input.num_rows, input.num_cols)
-            .filter(|neighbour| {
-                input.topology[neighbour.to_index(input.num_cols)].0 + 1
-                    >= input.topology[end.to_index(input.num_cols)].0
-            })
-            .collect();
-        let mut cost_to_dest = vec![None; input.num_cols as usize * input.num_rows as usize];
-        cost_to_dest[end.to_index(input.num_cols)] = Some(0);
+        Self::from_input_and_policy(input, MovementPolicy::default())
+    }
+    fn from_input_and_policy(input: Input, policy: MovementPolicy) -> Self {
+        let mut cost_to_dest = vec![None; input.num_cols * input.num_rows];
+        cost_to_dest[input.end.to_index(input.num_cols)] = Some(0);
+        let next_step = vec![None; cost_to_dest.len()];
         Self {
             input,
+            policy,
             cost_to_dest,
-            boundary,
+            next_step,
+        }
+    }
+    fn precompute(&mut self) {
+        if self.policy.is_weighted() {
+            self.precompute_by_dijkstra()
+        } else {
+            self.precompute_by_bfs()
+        }
+    }
+    // Level order BFS, backwards from the destination
+    fn precompute_by_bfs(&mut self) {
+        let end = self.input.end;
+        let mut visited = BitSet::with_capacity(self.cost_to_dest.len());
+        visited.insert(end.to_index(self.input.num_cols));
+        let mut queue = VecDeque::from([end]);
+        while let Some(pos) = queue.pop_front() {
+            let cost = self.cost_to_dest[pos.to_index(self.input.num_cols)].unwrap();
+            for (neighbour, step_cost) in self.input.predecessors(pos, &self.policy) {
+                let index = neighbour.to_index(self.input.num_cols);
+                if !visited.contains(index) {
+                    visited.insert(index);
+                    self.cost_to_dest[index] = Some(cost + step_cost);
+                    self.next_step[index] = Some(pos);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+    // Dijkstra, backwards from the destination: each position's parent in the search is the next
+    // step on its way to the destination
+    fn precompute_by_dijkstra(&mut self) {
+        let reached = dijkstra_all(&self.input.end, |pos| {
+            self.input
+                .predecessors(*pos, &self.policy)
+                .collect::<Vec<_>>()
+        });
+        for (pos, (next_step, cost)) in reached {
+            let index = pos.to_index(self.input.num_cols);
+            self.cost_to_dest[index] = Some(cost);
+            self.next_step[index] = Some(next_step);
         }
     }
     fn unexplored_neighbours<'a>(
         &'a self,
         pos: &'a Position,
     ) -> impl Iterator<Item = Position> + 'a {
-        pos.neighbours(self.input.num_rows, self.input.num_cols)
+        self.input
+            .neighbours(*pos, &self.policy)
             .filter(|neighbour| {
                 self.cost_to_dest[neighbour.to_index(self.input.num_cols)].is_none()
             })
@@ -137,48 +294,185 @@ impl PathFinder {
         &'a self,
         pos: &'a Position,
     ) -> impl Iterator<Item = Position> + 'a {
-        pos.neighbours(self.input.num_rows, self.input.num_cols)
+        self.input
+            .neighbours(*pos, &self.policy)
             .filter(|p| self.cost_to_dest[p.to_index(self.input.num_cols)].is_some())
             .filter(|neighbour| {
-                self.input.topology[pos.to_index(self.input.num_cols)].0 + 1
-                    >= self.input.topology[neighbour.to_index(self.input.num_cols)].0
+                self.policy
+                    .allows(self.input.elevation(*pos), self.input.elevation(*neighbour))
             })
     }
-    fn precompute(&mut self) {
-        while !self.boundary.is_empty() {
+    // Grow a boundary from the destination, deriving each cost from the explored neighbours.
+    // Quadratic in the worst case, and only correct for unweighted policies, so the Boundary
+    // variants only run it with the puzzle's policy.
+    fn precompute_by_boundary(&mut self) {
+        let end = self.input.end;
+        let mut boundary = self
+            .input
+            .predecessors(end, &self.policy)
+            .map(|(neighbour, _)| neighbour)
+            .collect::<VecDeque<_>>();
+        while let Some(pos) = boundary.pop_front() {
             // transition from boundary to cost to cost_to_dest
-            if let Some(pos) = self.boundary.pop_front() {
-                if let Some(cost) = self
-                    .explored_reachable_neighbours(&pos)
-                    .map(|neighbour| {
-                        self.cost_to_dest[neighbour.to_index(self.input.num_cols)].unwrap() + 1
-                    })
-                    .min()
-                {
-                    self.cost_to_dest[pos.to_index(self.input.num_cols)] = Some(cost); //.insert(pos, cost);
-                    for neighbour in self.unexplored_neighbours(&pos).collect::<Vec<_>>() {
-                        if !self.boundary.contains(&neighbour) {
-                            self.boundary.push_back(neighbour);
-                        }
+            if let Some((cost, next_step)) = self
+                .explored_reachable_neighbours(&pos)
+                .map(|neighbour| {
+                    (
+                        self.cost_to_dest[neighbour.to_index(self.input.num_cols)].unwrap()
+                            + self.policy.step_cost,
+                        neighbour,
+                    )
+                })
+                .min()
+            {
+                self.cost_to_dest[pos.to_index(self.input.num_cols)] = Some(cost);
+                self.next_step[pos.to_index(self.input.num_cols)] = Some(next_step);
+                for neighbour in self.unexplored_neighbours(&pos).collect::<Vec<_>>() {
+                    if !boundary.contains(&neighbour) {
+                        boundary.push_back(neighbour);
                     }
                 }
             }
         }
     }
-    fn shortest_path(mut self, start: Position) -> Output {
-        self.precompute();
-        self.cost_to_dest[start.to_index(self.input.num_cols)].unwrap()
+    fn shortest_path(&self, start: Position) -> Option<Output> {
+        self.cost_to_dest[start.to_index(self.input.num_cols)]
     }
-    fn shortest_path_from_any_a(mut self) -> Output {
-        self.precompute();
+    fn best_start_at_lowest_elevation(&self) -> Option<Position> {
         self.input
             .topology
             .iter()
             .enumerate()
-            .filter(|(_, elevation)| elevation.0 == 0)
-            .map(|(index, _)| self.cost_to_dest[index].unwrap_or(u64::MAX))
-            .min()
-            .unwrap()
+            .filter(|(index, elevation)| elevation.0 == 0 && self.cost_to_dest[*index].is_some())
+            .min_by_key(|(index, _)| self.cost_to_dest[*index])
+            .map(|(index, _)| Position::from_index_and_num_cols(index, self.input.num_cols))
+    }
+    fn shortest_path_from_any_a(&self) -> Option<Output> {
+        self.best_start_at_lowest_elevation()
+            .and_then(|start| self.shortest_path(start))
+    }
+    // All positions from start to destination along a shortest path, once precomputed.
+    // None if the destination can't be reached from start.
+    fn route(&self, start: Position) -> Option<Vec<Position>> {
+        self.cost_to_dest[start.to_index(self.input.num_cols)]?;
+        let mut route = vec![start];
+        let mut position = start;
+        while let Some(next_step) = self.next_step[position.to_index(self.input.num_cols)] {
+            route.push(next_step);
+            position = next_step;
+        }
+        Some(route)
+    }
+}
+
+// Forward search from start, guided by the distance to the destination: manhattan distance,
+// or chebyshev distance if diagonal moves are allowed
+fn a_star_shortest_path(input: &Input, policy: &MovementPolicy, start: Position) -> Option<Output> {
+    let distance = |pos: &Position| {
+        let (rows, cols) = (
+            pos.row.abs_diff(input.end.row),
+            pos.col.abs_diff(input.end.col),
+        );
+        let num_steps = if policy.diagonal_moves {
+            rows.max(cols)
+        } else {
+            rows + cols
+        };
+        num_steps as Output * policy.step_cost
+    };
+    astar(
+        &start,
+        |pos| input.successors(*pos, policy).collect::<Vec<_>>(),
+        distance,
+        |pos| *pos == input.end,
+    )
+    .map(|(_, cost)| cost)
+}
+
+fn render_route(input: &Input, route: &[Position], highlight: bool) -> String {
+    let mut cells = input
+        .topology
+        .iter()
+        .map(|elevation| ((elevation.0 + b'a') as char).to_string())
+        .collect::<Vec<_>>();
+    for step in route.windows(2) {
+        let arrow = match (
+            step[1].row as isize - step[0].row as isize,
+            step[1].col as isize - step[0].col as isize,
+        ) {
+            (-1, 0) => '^',
+            (1, 0) => 'v',
+            (0, -1) => '<',
+            (0, 1) => '>',
+            (-1, -1) | (1, 1) => '\\',
+            _ => '/',
+        };
+        cells[step[0].to_index(input.num_cols)] = if highlight {
+            format!("\x1b[1;31m{arrow}\x1b[0m")
+        } else {
+            arrow.to_string()
+        };
+    }
+    cells[input.end.to_index(input.num_cols)] = "E".to_string();
+    cells
+        .chunks(input.num_cols)
+        .map(|row| row.concat())
+        .intersperse("\n".to_string())
+        .collect()
+}
+
+pub enum RouteOutput {
+    // Print the routes with highlighted arrows
+    Terminal,
+    // Write the routes as plain text
+    File(PathBuf),
+}
+
+// Render the cheapest routes under the given policy for part 1 (from S) and part 2 (from the best
+// lowest point)
+pub fn render_shortest_routes(
+    data: &str,
+    policy: &MovementPolicy,
+    output: &RouteOutput,
+) -> Result<(), String> {
+    let input: Input = data.parse()?;
+    let mut path_finder = PathFinder::from_input_and_policy(input.clone(), *policy);
+    path_finder.precompute();
+    let highlight = matches!(output, RouteOutput::Terminal);
+    let start_route = path_finder
+        .route(input.start)
+        .ok_or_else(|| "No route from S to E".to_string())?;
+    let best_route = path_finder
+        .best_start_at_lowest_elevation()
+        .and_then(|start| path_finder.route(start))
+        .ok_or_else(|| "No route from any lowest point to E".to_string())?;
+    // Only mention the cost when it differs from the number of steps
+    let describe = |route: &[Position]| {
+        let (num_steps, cost) = (
+            route.len() - 1,
+            path_finder.shortest_path(route[0]).unwrap(),
+        );
+        if cost == num_steps as Output {
+            format!("{num_steps} steps")
+        } else {
+            format!("{num_steps} steps costing {cost}")
+        }
+    };
+    let rendered = format!(
+        "Part 1: {} from S at {:?}\n{}\n\nPart 2: {} from {:?}\n{}\n",
+        describe(&start_route),
+        (input.start.row, input.start.col),
+        render_route(&input, &start_route, highlight),
+        describe(&best_route),
+        (best_route[0].row, best_route[0].col),
+        render_route(&input, &best_route, highlight)
+    );
+    match output {
+        RouteOutput::Terminal => {
+            print!("{rendered}");
+            Ok(())
+        }
+        RouteOutput::File(path) => std::fs::write(path, rendered).map_err(|e| format!("{e}")),
     }
 }
 
@@ -192,18 +486,45 @@ fn parse_input(data: &str) -> Parsed {
 
 #[aoc(day12, part1)]
 fn part1(data: &Input) -> Output {
-    let start = data.start;
-    PathFinder::from_input(data.clone()).shortest_path(start)
+    let mut path_finder = PathFinder::from_input(data.clone());
+    path_finder.precompute();
+    path_finder.shortest_path(data.start).unwrap()
+}
+
+#[aoc(day12, part1, Boundary)]
+fn part1_boundary(data: &Input) -> Output {
+    let mut path_finder = PathFinder::from_input(data.clone());
+    path_finder.precompute_by_boundary();
+    path_finder.shortest_path(data.start).unwrap()
+}
+
+#[aoc(day12, part1, AStar)]
+fn part1_a_star(data: &Input) -> Output {
+    a_star_shortest_path(data, &MovementPolicy::default(), data.start).unwrap()
 }
 
 #[aoc(day12, part2)]
 fn part2(data: &Input) -> Output {
-    PathFinder::from_input(data.clone()).shortest_path_from_any_a()
+    let mut path_finder = PathFinder::from_input(data.clone());
+    path_finder.precompute();
+    path_finder.shortest_path_from_any_a().unwrap()
+}
+
+#[aoc(day12, part2, Boundary)]
+fn part2_boundary(data: &Input) -> Output {
+    let mut path_finder = PathFinder::from_input(data.clone());
+    path_finder.precompute_by_boundary();
+    path_finder.shortest_path_from_any_a().unwrap()
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate test;
+
     use super::*;
+    use crate::random::XorShift;
+    use std::cmp::Ordering;
+    use test::Bencher;
 
     const EXAMPLE_SOLUTION_PART1: Output = 31;
     const SOLUTION_PART1: Output = 350;
@@ -232,4 +553,248 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input()), SOLUTION_PART2)
     }
+    #[test]
+    fn test_part1_boundary_given_example_input() {
+        assert_eq!(part1_boundary(&example_input()), EXAMPLE_SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part1_boundary() {
+        assert_eq!(part1_boundary(&input()), SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part1_a_star_given_example_input() {
+        assert_eq!(part1_a_star(&example_input()), EXAMPLE_SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part1_a_star() {
+        assert_eq!(part1_a_star(&input()), SOLUTION_PART1)
+    }
+    #[test]
+    fn test_part2_boundary_given_example_input() {
+        assert_eq!(part2_boundary(&example_input()), EXAMPLE_SOLUTION_PART2)
+    }
+    #[test]
+    fn test_part2_boundary() {
+        assert_eq!(part2_boundary(&input()), SOLUTION_PART2)
+    }
+    // A slope rising from S in the top left corner to E in the bottom right corner, with random
+    // dips and walls making some steps too steep to climb
+    fn generated_map(num_rows: usize, num_cols: usize, seed: u64) -> String {
+        let mut rng = XorShift::new(seed);
+        (0..num_rows)
+            .map(|row| {
+                (0..num_cols)
+                    .map(|col| {
+                        if (row, col) == (0, 0) {
+                            'S'
+                        } else if (row, col) == (num_rows - 1, num_cols - 1) {
+                            'E'
+                        } else {
+                            let slope = (row + col) * 25 / (num_rows + num_cols - 2);
+                            let elevation = match rng.below(8) {
+                                // A wall, too high to climb from its neighbours
+                                0 | 1 => (slope + 2).min(25),
+                                r => slope.saturating_sub(r as usize % 2),
+                            };
+                            (b'a' + elevation as u8) as char
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .intersperse("\n".to_string())
+            .collect()
+    }
+    fn shortest_paths(
+        input: &Input,
+        precompute: fn(&mut PathFinder),
+    ) -> (Option<Output>, Option<Output>) {
+        let mut path_finder = PathFinder::from_input(input.clone());
+        precompute(&mut path_finder);
+        (
+            path_finder.shortest_path(input.start),
+            path_finder.shortest_path_from_any_a(),
+        )
+    }
+    #[test]
+    fn test_variants_agree_on_generated_maps() {
+        for seed in 1..20 {
+            let input: Input = generated_map(40, 60, seed).parse().unwrap();
+            let bfs = shortest_paths(&input, PathFinder::precompute);
+            assert_eq!(
+                bfs,
+                shortest_paths(&input, PathFinder::precompute_by_boundary)
+            );
+            assert_eq!(
+                bfs.0,
+                a_star_shortest_path(&input, &MovementPolicy::default(), input.start)
+            );
+        }
+    }
+    fn large_generated_map() -> Input {
+        let input: Input = generated_map(250, 250, 12).parse().unwrap();
+        assert!(a_star_shortest_path(&input, &MovementPolicy::default(), input.start).is_some());
+        input
+    }
+    #[test]
+    fn test_map_larger_than_u16_indices() {
+        // 300x300 positions used to overflow u16 indices
+        let input: Input = generated_map(300, 300, 1).parse().unwrap();
+        assert_eq!(input.end, Position { row: 299, col: 299 });
+        let shortest_path = a_star_shortest_path(&input, &MovementPolicy::default(), input.start);
+        assert!(shortest_path.is_some());
+        assert_eq!(
+            shortest_paths(&input, PathFinder::precompute).0,
+            shortest_path
+        );
+    }
+    #[bench]
+    fn bench_part1_on_large_generated_map(b: &mut Bencher) {
+        let input = large_generated_map();
+        b.iter(|| part1(&input))
+    }
+    #[bench]
+    fn bench_part1_boundary_on_large_generated_map(b: &mut Bencher) {
+        let input = large_generated_map();
+        b.iter(|| part1_boundary(&input))
+    }
+    #[bench]
+    fn bench_part1_a_star_on_large_generated_map(b: &mut Bencher) {
+        let input = large_generated_map();
+        b.iter(|| part1_a_star(&input))
+    }
+    #[test]
+    fn test_malformed_height_maps() {
+        let error = |s: &str| s.parse::<Input>().err();
+        assert_eq!(error(""), Some("No input line".to_string()));
+        assert_eq!(
+            error("Sab\nabc\nabE"),
+            None,
+            "A well formed map should parse"
+        );
+        assert_eq!(
+            error("Sab\nabcd\nabE"),
+            Some("Line 2 has 4 columns, expected 3".to_string())
+        );
+        assert_eq!(
+            error("Sab\nab\nabE"),
+            Some("Line 2 has 2 columns, expected 3".to_string())
+        );
+        // A long first line followed by many short ones doesn't allocate for the whole rectangle
+        let ragged = format!("S{}E{}", "a".repeat(999_998), "\n".repeat(1_000_000));
+        assert_eq!(
+            error(&ragged),
+            Some("Line 2 has 0 columns, expected 1000000".to_string())
+        );
+        assert_eq!(error("Sab\nabc\nabc"), Some("Missing end".to_string()));
+        assert_eq!(error("aab\nabc\nabE"), Some("Missing start".to_string()));
+        assert_eq!(
+            error("Sab\naSc\nabE"),
+            Some(
+                "Duplicate start at line 2, column 2, already seen at line 1, column 1".to_string()
+            )
+        );
+        assert_eq!(
+            error("SaE\nabc\nabE"),
+            Some("Duplicate end at line 3, column 3, already seen at line 1, column 3".to_string())
+        );
+        assert_eq!(
+            error("Sab\nabC\nabE"),
+            Some("Invalid elevation: 'C' at line 2, column 3".to_string())
+        );
+    }
+    #[test]
+    fn test_diagonal_moves() {
+        let policy = MovementPolicy {
+            diagonal_moves: true,
+            ..Default::default()
+        };
+        for input in [example_input(), input()] {
+            let mut path_finder = PathFinder::from_input_and_policy(input.clone(), policy);
+            path_finder.precompute();
+            let shortest_path = path_finder.shortest_path(input.start);
+            assert_eq!(
+                shortest_path,
+                a_star_shortest_path(&input, &policy, input.start)
+            );
+            assert!(shortest_path.unwrap() < part1(&input));
+        }
+    }
+    #[test]
+    fn test_dijkstra_agrees_with_bfs_for_unweighted_policy() {
+        for input in [example_input(), input()] {
+            assert_eq!(
+                shortest_paths(&input, PathFinder::precompute_by_dijkstra),
+                shortest_paths(&input, PathFinder::precompute_by_bfs)
+            );
+        }
+    }
+    #[test]
+    fn test_weighted_policy() {
+        // Straight along the top row is 27 steps, but it dips from c to b once.
+        // Detouring through the bottom row avoids the dip at the cost of 2 extra steps.
+        let input: Input = "SbcbcdefghijklmnopqrstuvwxyE\nabcccdefghijklmnopqrstuvwxyz"
+            .parse()
+            .unwrap();
+        assert_eq!(part1(&input), 27);
+        let policy = MovementPolicy {
+            descent_cost: 10,
+            ..Default::default()
+        };
+        let mut path_finder = PathFinder::from_input_and_policy(input.clone(), policy);
+        path_finder.precompute();
+        assert_eq!(path_finder.shortest_path(input.start), Some(29));
+        assert_eq!(a_star_shortest_path(&input, &policy, input.start), Some(29));
+        assert!(path_finder
+            .route(input.start)
+            .unwrap()
+            .iter()
+            .any(|pos| pos.row == 1));
+        // Forbidding any descent leaves the detour as the only way
+        let policy = MovementPolicy {
+            max_descent: 0,
+            ..Default::default()
+        };
+        assert_eq!(a_star_shortest_path(&input, &policy, input.start), Some(29));
+    }
+    #[test]
+    fn test_route_given_example_input() {
+        let input = example_input();
+        let mut path_finder = PathFinder::from_input(input.clone());
+        path_finder.precompute();
+        let route = path_finder.route(input.start).unwrap();
+        assert_eq!(route.len() as Output, EXAMPLE_SOLUTION_PART1 + 1);
+        assert_eq!(route.first(), Some(&input.start));
+        assert_eq!(route.last(), Some(&input.end));
+        for step in route.windows(2) {
+            assert!(step[0]
+                .neighbours(input.num_rows, input.num_cols, false)
+                .any(|neighbour| neighbour == step[1]));
+            assert!(
+                input.topology[step[0].to_index(input.num_cols)].0 + 1
+                    >= input.topology[step[1].to_index(input.num_cols)].0
+            );
+        }
+        let rendered = render_route(&input, &route, false);
+        assert_eq!(rendered.lines().count(), 5);
+        // Each step of the route is drawn as an arrow towards the next one
+        let cells = rendered
+            .lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for step in route.windows(2) {
+            let arrow = match (step[1].row.cmp(&step[0].row), step[1].col.cmp(&step[0].col)) {
+                (Ordering::Less, Ordering::Equal) => '^',
+                (Ordering::Greater, Ordering::Equal) => 'v',
+                (Ordering::Equal, Ordering::Less) => '<',
+                (Ordering::Equal, Ordering::Greater) => '>',
+                other => panic!("{other:?} isn't a single step"),
+            };
+            assert_eq!(cells[step[0].row][step[0].col], arrow);
+        }
+        let best_start = path_finder.best_start_at_lowest_elevation().unwrap();
+        assert_eq!(
+            path_finder.route(best_start).unwrap().len() as Output,
+            EXAMPLE_SOLUTION_PART2 + 1
+        );
+    }
 }
@@ -1,19 +1,92 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
-use serde_derive::{Deserialize, Serialize};
+use crate::input_parser::ByteCursor;
 use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 type Parsed = Vec<(Token, Token)>;
 type Input = [(Token, Token)];
 type Output = usize;
 
-#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug)]
-#[serde(untagged)]
+#[derive(Clone, Eq, PartialEq, Debug)]
 enum Token {
-    Scalar(i8),
+    Scalar(u64),
     List(Vec<Token>),
 }
 
+// Recursive descent parser for a single packet. Whitespace is allowed between any two tokens.
+struct PacketParser<'a> {
+    cursor: ByteCursor<'a>,
+}
+
+impl<'a> PacketParser<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            cursor: ByteCursor::new(s),
+        }
+    }
+    fn parse_list(&mut self) -> Result<Token, String> {
+        // Skip the opening bracket
+        self.cursor.advance(1);
+        let mut list = Vec::new();
+        if self.cursor.peek() == Some(b']') {
+            self.cursor.advance(1);
+            return Ok(Token::List(list));
+        }
+        loop {
+            list.push(self.parse_token()?);
+            match self.cursor.peek() {
+                Some(b',') => self.cursor.advance(1),
+                Some(b']') => {
+                    self.cursor.advance(1);
+                    return Ok(Token::List(list));
+                }
+                _ => return Err(self.cursor.error("',' or ']'")),
+            }
+        }
+    }
+    fn parse_token(&mut self) -> Result<Token, String> {
+        match self.cursor.peek() {
+            Some(b'[') => self.parse_list(),
+            Some(b) if b.is_ascii_digit() => self.cursor.parse_unsigned().map(Token::Scalar),
+            _ => Err(self.cursor.error("'[' or a digit")),
+        }
+    }
+    fn parse_packet(mut self) -> Result<Token, String> {
+        let token = self.parse_token()?;
+        match self.cursor.peek() {
+            None => Ok(token),
+            Some(_) => Err(self.cursor.error("end of packet")),
+        }
+    }
+}
+
+impl FromStr for Token {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PacketParser::new(s).parse_packet()
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Scalar(x) => write!(f, "{x}"),
+            Token::List(list) => {
+                write!(f, "[")?;
+                for (i, token) in list.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{token}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
 impl Ord for Token {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
@@ -44,15 +117,291 @@ impl PartialOrd for Token {
     }
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+// What settled a comparison, in the puzzle's own words
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Verdict {
+    LeftSmaller,
+    RightSmaller,
+    LeftRanOut,
+    RightRanOut,
+}
+
+impl Verdict {
+    fn ordering(self) -> Ordering {
+        match self {
+            Self::LeftSmaller | Self::LeftRanOut => Ordering::Less,
+            Self::RightSmaller | Self::RightRanOut => Ordering::Greater,
+        }
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LeftSmaller => write!(f, "Left side is smaller"),
+            Self::RightSmaller => write!(f, "Right side is smaller"),
+            Self::LeftRanOut => write!(f, "Left side ran out of items"),
+            Self::RightRanOut => write!(f, "Right side ran out of items"),
+        }?;
+        match self.ordering() {
+            Ordering::Less => write!(f, ", so inputs are in the right order"),
+            _ => write!(f, ", so inputs are not in the right order"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct ComparisonStep {
+    // Indices of the compared values within the enclosing lists of each packet
+    path: Vec<usize>,
+    // How deep in the explanation this step is: promoting a scalar to a list adds one level
+    nesting: usize,
+    left: Token,
+    right: Token,
+    // Set if the two sides had mixed types, so this side's scalar was promoted to a list
+    promoted: Option<Side>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct ComparisonTrace {
+    steps: Vec<ComparisonStep>,
+    // None if both packets are equal
+    verdict: Option<Verdict>,
+    // Index of the step that settled the comparison
+    deciding_step: usize,
+}
+
+impl Display for ComparisonTrace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let indent = |nesting| "  ".repeat(nesting);
+        for (i, step) in self.steps.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{}- Compare {} vs {}",
+                indent(step.nesting),
+                step.left,
+                step.right
+            )?;
+            match step.promoted {
+                Some(Side::Left) => write!(
+                    f,
+                    "\n{}- Mixed types; convert left to [{}] and retry comparison",
+                    indent(step.nesting + 1),
+                    step.left
+                )?,
+                Some(Side::Right) => write!(
+                    f,
+                    "\n{}- Mixed types; convert right to [{}] and retry comparison",
+                    indent(step.nesting + 1),
+                    step.right
+                )?,
+                None => {}
+            }
+        }
+        // The verdict always comes last, as the comparison stops as soon as it's settled
+        match self.verdict {
+            Some(verdict) => write!(
+                f,
+                "\n{}- {verdict}",
+                indent(self.steps[self.deciding_step].nesting + 1)
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Token {
+    fn explain_cmp(&self, other: &Self) -> ComparisonTrace {
+        let mut trace = ComparisonTrace {
+            steps: Vec::new(),
+            verdict: None,
+            deciding_step: 0,
+        };
+        trace.verdict = Self::explain_cmp_at(self, other, &mut Vec::new(), 0, &mut trace);
+        trace
+    }
+    fn explain_cmp_at(
+        left: &Self,
+        right: &Self,
+        path: &mut Vec<usize>,
+        nesting: usize,
+        trace: &mut ComparisonTrace,
+    ) -> Option<Verdict> {
+        let step = trace.steps.len();
+        trace.steps.push(ComparisonStep {
+            path: path.clone(),
+            nesting,
+            left: left.clone(),
+            right: right.clone(),
+            promoted: None,
+        });
+        trace.deciding_step = step;
+        match (left, right) {
+            (Token::Scalar(l), Token::Scalar(r)) => match l.cmp(r) {
+                Ordering::Less => Some(Verdict::LeftSmaller),
+                Ordering::Greater => Some(Verdict::RightSmaller),
+                Ordering::Equal => None,
+            },
+            (Token::List(l), Token::List(r)) => {
+                for index in 0..l.len().max(r.len()) {
+                    let (l, r) = match (l.get(index), r.get(index)) {
+                        (Some(l), Some(r)) => (l, r),
+                        (None, _) => {
+                            trace.deciding_step = step;
+                            return Some(Verdict::LeftRanOut);
+                        }
+                        (_, None) => {
+                            trace.deciding_step = step;
+                            return Some(Verdict::RightRanOut);
+                        }
+                    };
+                    path.push(index);
+                    let verdict = Self::explain_cmp_at(l, r, path, nesting + 1, trace);
+                    path.pop();
+                    if verdict.is_some() {
+                        return verdict;
+                    }
+                }
+                None
+            }
+            (Token::Scalar(_), Token::List(_)) => {
+                trace.steps[step].promoted = Some(Side::Left);
+                let left = Token::List(vec![left.clone()]);
+                Self::explain_cmp_at(&left, right, path, nesting + 1, trace)
+            }
+            (Token::List(_), Token::Scalar(_)) => {
+                trace.steps[step].promoted = Some(Side::Right);
+                let right = Token::List(vec![right.clone()]);
+                Self::explain_cmp_at(left, &right, path, nesting + 1, trace)
+            }
+        }
+    }
+}
+
+// Explain the comparison of each pair of packets, in the format of the puzzle's worked example
+pub fn explain_pairs(data: &str) -> Result<String, String> {
+    data.split("\n\n")
+        .enumerate()
+        .map(|(i, pair)| {
+            let (left, right) = pair
+                .trim_end()
+                .split_once('\n')
+                .ok_or_else(|| format!("Pair {} should span two lines", i + 1))?;
+            let trace = left.parse::<Token>()?.explain_cmp(&right.parse()?);
+            Ok(format!("== Pair {} ==\n{trace}", i + 1))
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(|explanations| explanations.join("\n\n"))
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Lexeme {
+    Open,
+    Close,
+    Scalar(u64),
+}
+
+// Walks a raw packet string one lexeme at a time, without allocating.
+// A scalar that gets compared to a list is replayed, followed by a virtual closing bracket, as if
+// it had been written as a single element list.
+struct LexemeCursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    replayed_scalar: Option<u64>,
+    virtual_closes: usize,
+}
+
+impl<'a> LexemeCursor<'a> {
+    fn new(packet: &'a str) -> Self {
+        Self {
+            bytes: packet.as_bytes(),
+            position: 0,
+            replayed_scalar: None,
+            virtual_closes: 0,
+        }
+    }
+    fn promote_to_list(&mut self, scalar: u64) {
+        self.replayed_scalar = Some(scalar);
+        self.virtual_closes += 1;
+    }
+}
+
+impl Iterator for LexemeCursor<'_> {
+    type Item = Lexeme;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(scalar) = self.replayed_scalar.take() {
+            return Some(Lexeme::Scalar(scalar));
+        }
+        if self.virtual_closes > 0 {
+            self.virtual_closes -= 1;
+            return Some(Lexeme::Close);
+        }
+        while let Some(b) = self.bytes.get(self.position) {
+            self.position += 1;
+            match b {
+                b'[' => return Some(Lexeme::Open),
+                b']' => return Some(Lexeme::Close),
+                b'0'..=b'9' => {
+                    let mut scalar = (b - b'0') as u64;
+                    while let Some(digit @ b'0'..=b'9') = self.bytes.get(self.position) {
+                        scalar = scalar * 10 + (digit - b'0') as u64;
+                        self.position += 1;
+                    }
+                    return Some(Lexeme::Scalar(scalar));
+                }
+                // Separators and whitespace
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+// Same ordering as `Token::cmp`, computed directly on well formed raw packets
+fn compare_raw_packets(left: &str, right: &str) -> Ordering {
+    let mut left = LexemeCursor::new(left);
+    let mut right = LexemeCursor::new(right);
+    loop {
+        match (left.next(), right.next()) {
+            (Some(Lexeme::Scalar(l)), Some(Lexeme::Scalar(r))) => {
+                if l != r {
+                    return l.cmp(&r);
+                }
+            }
+            (Some(Lexeme::Open), Some(Lexeme::Open))
+            | (Some(Lexeme::Close), Some(Lexeme::Close)) => {}
+            (Some(Lexeme::Scalar(l)), Some(Lexeme::Open)) => left.promote_to_list(l),
+            (Some(Lexeme::Open), Some(Lexeme::Scalar(r))) => right.promote_to_list(r),
+            // The left list ran out of items first
+            (Some(Lexeme::Close), Some(_)) | (None, Some(_)) => return Ordering::Less,
+            (Some(_), Some(Lexeme::Close)) | (Some(_), None) => return Ordering::Greater,
+            (None, None) => return Ordering::Equal,
+        }
+    }
+}
+
+fn divider_packets() -> [Token; 2] {
+    [
+        Token::List(vec![Token::List(vec![Token::Scalar(2)])]),
+        Token::List(vec![Token::List(vec![Token::Scalar(6)])]),
+    ]
+}
+
 #[aoc_generator(day13)]
 fn parse_input(data: &str) -> Parsed {
     data.split("\n\n")
         .map(|s| {
             let (left, right) = s.split_once('\n').unwrap();
-            (
-                serde_json::from_str::<Token>(left).unwrap(),
-                serde_json::from_str::<Token>(right).unwrap(),
-            )
+            (left.parse().unwrap(), right.parse().unwrap())
         })
         .collect()
 }
@@ -66,17 +415,14 @@ fn part1(data: &Input) -> Output {
         .sum()
 }
 
-#[aoc(day13, part2)]
-fn part2(data: &Input) -> Output {
+#[aoc(day13, part2, Sorting)]
+fn part2_sorting(data: &Input) -> Output {
     let mut all_packets = data
         .iter()
         .flat_map(|(left, right)| vec![left, right])
         .cloned()
         .collect::<Vec<_>>();
-    let delims = vec![
-        Token::List(vec![Token::List(vec![Token::Scalar(2)])]),
-        Token::List(vec![Token::List(vec![Token::Scalar(6)])]),
-    ];
+    let delims = divider_packets();
     all_packets.extend(delims.clone());
     all_packets.sort();
     all_packets
@@ -87,9 +433,50 @@ fn part2(data: &Input) -> Output {
         .product()
 }
 
+// The index of a divider in the sorted list is one more than the number of packets sorting
+// before it, so there is no need to sort at all.
+#[aoc(day13, part2, Counting)]
+fn part2(data: &Input) -> Output {
+    let [first, second] = divider_packets();
+    let (mut before_first, mut before_second) = (0, 0);
+    for packet in data.iter().flat_map(|(left, right)| [left, right]) {
+        if *packet < first {
+            before_first += 1;
+        } else if *packet < second {
+            before_second += 1;
+        }
+    }
+    // The first divider also sorts before the second one
+    (before_first + 1) * (before_first + before_second + 2)
+}
+
+// The generator copies the raw input once; after that, the zero copy variant compares packets
+// in place, without building tokens
+#[aoc_generator(day13, part2, ZeroCopy)]
+fn raw_input(data: &str) -> String {
+    data.to_string()
+}
+
+#[aoc(day13, part2, ZeroCopy)]
+fn part2_zero_copy(data: &str) -> Output {
+    let (mut before_first, mut before_second) = (0, 0);
+    for packet in data.lines().filter(|line| !line.is_empty()) {
+        if compare_raw_packets(packet, "[[2]]").is_lt() {
+            before_first += 1;
+        } else if compare_raw_packets(packet, "[[6]]").is_lt() {
+            before_second += 1;
+        }
+    }
+    (before_first + 1) * (before_first + before_second + 2)
+}
+
 #[cfg(test)]
 mod tests {
+    extern crate test;
+
     use super::*;
+    use crate::random::XorShift;
+    use test::Bencher;
 
     const EXAMPLE_SOLUTION_PART1: Output = 13;
     const SOLUTION_PART1: Output = 5393;
@@ -118,4 +505,125 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input()), SOLUTION_PART2)
     }
+    #[test]
+    fn test_part2_sorting_given_example_input() {
+        assert_eq!(part2_sorting(&example_input()), EXAMPLE_SOLUTION_PART2)
+    }
+    #[test]
+    fn test_part2_sorting() {
+        assert_eq!(part2_sorting(&input()), SOLUTION_PART2)
+    }
+    #[test]
+    fn test_part2_zero_copy_given_example_input() {
+        assert_eq!(
+            part2_zero_copy(include_str!("../example/2022/day13.txt")),
+            EXAMPLE_SOLUTION_PART2
+        )
+    }
+    #[test]
+    fn test_part2_zero_copy() {
+        assert_eq!(
+            part2_zero_copy(include_str!("../input/2022/day13.txt")),
+            SOLUTION_PART2
+        )
+    }
+    #[bench]
+    fn bench_part2(b: &mut Bencher) {
+        let input = input();
+        b.iter(|| part2(&input))
+    }
+    #[bench]
+    fn bench_part2_sorting(b: &mut Bencher) {
+        let input = input();
+        b.iter(|| part2_sorting(&input))
+    }
+    #[bench]
+    fn bench_part2_zero_copy(b: &mut Bencher) {
+        let input = include_str!("../input/2022/day13.txt");
+        b.iter(|| part2_zero_copy(input))
+    }
+    #[test]
+    fn test_explain_cmp() {
+        let pairs = example_input();
+        let trace = pairs[7].0.explain_cmp(&pairs[7].1);
+        assert_eq!(trace.verdict, Some(Verdict::RightSmaller));
+        assert_eq!(trace.steps[trace.deciding_step].path, vec![1, 1, 1, 1, 2]);
+        assert_eq!(trace.steps[trace.deciding_step].left, Token::Scalar(7));
+        assert_eq!(trace.steps[trace.deciding_step].right, Token::Scalar(0));
+        let trace = pairs[1].0.explain_cmp(&pairs[1].1);
+        assert_eq!(trace.steps[3].path, vec![1]);
+        assert_eq!(trace.steps[3].promoted, Some(Side::Right));
+        for (left, right) in pairs.iter().chain(input().iter()) {
+            assert_eq!(
+                left.explain_cmp(right)
+                    .verdict
+                    .map_or(Ordering::Equal, Verdict::ordering),
+                left.cmp(right)
+            );
+        }
+    }
+    #[test]
+    fn test_explain_pairs_given_example_input() {
+        assert_eq!(
+            explain_pairs(include_str!("../example/2022/day13.txt")).unwrap(),
+            include_str!("../example/2022/day13_explained.txt").trim_end()
+        );
+    }
+    #[test]
+    fn test_parse_packet() {
+        assert_eq!(
+            " [ 1000 ,[],\t[255]] ".parse(),
+            Ok(Token::List(vec![
+                Token::Scalar(1000),
+                Token::List(vec![]),
+                Token::List(vec![Token::Scalar(255)])
+            ]))
+        );
+        assert!("[200]".parse::<Token>().unwrap() > "[[128]]".parse::<Token>().unwrap());
+        assert_eq!(
+            "[1,[2;3]]".parse::<Token>(),
+            Err("Expected ',' or ']' at position 5, found ';'".to_string())
+        );
+        assert_eq!(
+            "[1,".parse::<Token>(),
+            Err("Expected '[' or a digit at position 3, found end of input".to_string())
+        );
+        assert_eq!(
+            "[1]]".parse::<Token>(),
+            Err("Expected end of packet at position 3, found ']'".to_string())
+        );
+    }
+    fn random_packet(rng: &mut XorShift, depth: u32, max_scalar: u64) -> Token {
+        if depth == 0 || rng.below(3) == 0 {
+            Token::Scalar(rng.below(max_scalar))
+        } else {
+            Token::List(
+                (0..rng.below(5))
+                    .map(|_| random_packet(rng, depth - 1, max_scalar))
+                    .collect(),
+            )
+        }
+    }
+    #[test]
+    fn test_display_round_trips() {
+        let mut rng = XorShift::new(13);
+        for _ in 0..1000 {
+            let packet = random_packet(&mut rng, 5, u64::MAX);
+            assert_eq!(packet.to_string().parse(), Ok(packet));
+        }
+        assert_eq!(example_input()[1].1.to_string(), "[[1],4]");
+    }
+    #[test]
+    fn test_compare_raw_packets_agrees_with_tokens() {
+        let mut rng = XorShift::new(29);
+        for _ in 0..1000 {
+            // Small scalars and shallow lists, so that many pairs share a common prefix
+            let (left, right) = (random_packet(&mut rng, 3, 4), random_packet(&mut rng, 3, 4));
+            assert_eq!(
+                compare_raw_packets(&left.to_string(), &right.to_string()),
+                left.cmp(&right),
+                "{left} vs {right}"
+            );
+        }
+    }
 }
@@ -1,7 +1,11 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::frame_limiter::FrameLimiter;
+use crate::image_writer::{write_ppm, Rgb};
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::iter::once;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -14,17 +18,34 @@ impl Point {
     fn to_index(self, num_cols: usize) -> usize {
         (self.row * num_cols + self.col) as usize
     }
+    // Bresenham's line algorithm: exact for horizontal, vertical and 45° segments, and a best
+    // approximation for any other slope
     fn line_to(self, other: Self) -> Vec<Point> {
-        if self.row == other.row {
-            ((self.col.min(other.col))..=(self.col.max(other.col)))
-                .map(|col| Point { row: self.row, col })
-                .collect::<Vec<_>>()
-        } else if self.col == other.col {
-            ((self.row.min(other.row))..=(self.row.max(other.row)))
-                .map(|row| Point { row, col: self.col })
-                .collect::<Vec<_>>()
-        } else {
-            panic!("Only works for segments");
+        let (mut row, mut col) = (self.row as isize, self.col as isize);
+        let (end_row, end_col) = (other.row as isize, other.col as isize);
+        let delta_col = (end_col - col).abs();
+        let delta_row = -(end_row - row).abs();
+        let step_col = if col < end_col { 1 } else { -1 };
+        let step_row = if row < end_row { 1 } else { -1 };
+        let mut error = delta_col + delta_row;
+        let mut points = Vec::new();
+        loop {
+            points.push(Point {
+                row: row as usize,
+                col: col as usize,
+            });
+            if row == end_row && col == end_col {
+                return points;
+            }
+            let doubled_error = 2 * error;
+            if doubled_error >= delta_row {
+                error += delta_row;
+                col += step_col;
+            }
+            if doubled_error <= delta_col {
+                error += delta_col;
+                row += step_row;
+            }
         }
     }
 }
@@ -36,8 +57,12 @@ impl FromStr for Point {
             .split_once(',')
             .ok_or(format!("Failed to parse point: {s}"))?;
         Ok(Self {
-            row: row.parse().map_err(|e| format!("{e}"))?,
-            col: col.parse().map_err(|e| format!("{e}"))?,
+            row: row
+                .parse()
+                .map_err(|e| format!("Invalid row in point {s}: {e}"))?,
+            col: col
+                .parse()
+                .map_err(|e| format!("Invalid column in point {s}: {e}"))?,
         })
     }
 }
@@ -59,6 +84,16 @@ impl Into<char> for Tile {
     }
 }
 
+impl Tile {
+    fn colour(self) -> Rgb {
+        match self {
+            Self::Air => [20, 20, 30],
+            Self::Rock => [120, 120, 120],
+            Self::Sand => [230, 200, 90],
+        }
+    }
+}
+
 #[derive(Clone)]
 struct Map {
     num_rows: usize,
@@ -73,16 +108,21 @@ impl FromStr for Map {
         let mut points = s
             .trim_end()
             .split('\n')
-            .flat_map(|line| {
+            .enumerate()
+            .map(|(line_index, line)| {
                 let points = line
                     .split(" -> ")
-                    .map(|p| Point::from_str(p).unwrap())
-                    .collect::<Vec<_>>();
-                points
+                    .map(|p| p.parse::<Point>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Line {}: {e}", line_index + 1))?;
+                Ok(points
                     .windows(2)
                     .flat_map(|window| window[0].line_to(window[1]))
-                    .collect::<Vec<_>>()
+                    .collect::<Vec<_>>())
             })
+            .collect::<Result<Vec<_>, String>>()?
+            .into_iter()
+            .flatten()
             .collect::<Vec<Point>>();
         let max_rows = points
             .iter()
@@ -96,7 +136,12 @@ impl FromStr for Map {
         // One to the left of infinite floor
         points.push(Point {
             row: max_rows + 2,
-            col: 500 - max_rows - 2,
+            col: 500usize.checked_sub(max_rows + 2).ok_or_else(|| {
+                format!(
+                    "Rock at row {max_rows} is too deep: the floor below would extend left of \
+                     column 0"
+                )
+            })?,
         });
         // One to the right of infinite floor
         points.push(Point {
@@ -151,6 +196,32 @@ impl Map {
         }
         self
     }
+    fn total_rows(&self) -> usize {
+        self.tiles.len() / self.num_cols
+    }
+    // Render at most `num_rows` x `num_cols` tiles, centered on `focus` where the map allows it
+    fn render_window(&self, focus: Point, num_rows: usize, num_cols: usize) -> String {
+        let num_rows = num_rows.min(self.total_rows());
+        let num_cols = num_cols.min(self.num_cols);
+        let top = focus
+            .row
+            .saturating_sub(num_rows / 2)
+            .min(self.total_rows() - num_rows);
+        let left = focus
+            .col
+            .saturating_sub(num_cols / 2)
+            .min(self.num_cols - num_cols);
+        (top..top + num_rows)
+            .map(|row| {
+                (left..left + num_cols)
+                    .map(|col| {
+                        Into::<char>::into(self.tiles[Point { row, col }.to_index(self.num_cols)])
+                    })
+                    .chain(once('\n'))
+                    .collect::<String>()
+            })
+            .collect()
+    }
 }
 
 impl Debug for Map {
@@ -212,20 +283,138 @@ fn find_next_sand_position(caves: &Map, mut position: Point) -> Option<Point> {
     }
 }
 
+// Drops one grain of sand per step and yields the position where it came to rest
+struct Sandfall<'a> {
+    caves: &'a mut Map,
+    start: Point,
+    source_blocked: bool,
+}
+
+impl<'a> Sandfall<'a> {
+    fn new(caves: &'a mut Map) -> Self {
+        let start = Point {
+            row: 0,
+            col: 500 - caves.min_col,
+        };
+        Self {
+            caves,
+            start,
+            source_blocked: false,
+        }
+    }
+    fn caves(&self) -> &Map {
+        self.caves
+    }
+}
+
+impl Iterator for Sandfall<'_> {
+    type Item = Point;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.source_blocked {
+            return None;
+        }
+        let sand_position = find_next_sand_position(self.caves, self.start)?;
+        self.caves.tiles[sand_position.to_index(self.caves.num_cols)] = Tile::Sand;
+        self.source_blocked = sand_position == self.start;
+        Some(sand_position)
+    }
+}
+
 fn simulate_sandfall(caves: &mut Map) -> usize {
-    let mut count = 0;
-    let start = Point {
-        row: 0,
-        col: 500 - caves.min_col,
-    };
-    while let Some(sand_position) = find_next_sand_position(caves, start) {
-        caves.tiles[sand_position.to_index(caves.num_cols)] = Tile::Sand;
-        count += 1;
-        if sand_position == start {
-            return count;
+    Sandfall::new(caves).count()
+}
+
+pub enum FrameOutput {
+    // Redraw a viewport following the sand, at most `max_fps` times per second
+    Terminal {
+        max_fps: u32,
+        viewport_rows: usize,
+        viewport_cols: usize,
+    },
+    // Write one `frame_NNNNN.ppm` file per frame in the given directory
+    PpmFrames(PathBuf),
+}
+
+pub struct Visualisation {
+    pub with_infinite_floor: bool,
+    pub grains_per_frame: usize,
+    pub output: FrameOutput,
+}
+
+impl Visualisation {
+    fn render_frame(
+        &self,
+        caves: &Map,
+        focus: Point,
+        frame_index: usize,
+        num_grains: usize,
+        frame_limiter: &mut Option<FrameLimiter>,
+    ) -> Result<(), String> {
+        match &self.output {
+            FrameOutput::Terminal {
+                max_fps,
+                viewport_rows,
+                viewport_cols,
+            } => frame_limiter
+                .get_or_insert_with(|| FrameLimiter::new(*max_fps))
+                .draw_frame(&format!(
+                    "{}grains at rest: {num_grains}",
+                    caves.render_window(focus, *viewport_rows, *viewport_cols)
+                )),
+            FrameOutput::PpmFrames(directory) => write_ppm(
+                &directory.join(format!("frame_{frame_index:05}.ppm")),
+                caves.num_cols,
+                caves.total_rows(),
+                &caves
+                    .tiles
+                    .iter()
+                    .map(|tile| tile.colour())
+                    .collect::<Vec<_>>(),
+            )
+            .map_err(|e| format!("{e}")),
         }
     }
-    count
+    // Run the simulation, rendering a frame every `grains_per_frame` grains.
+    // Returns the number of grains at rest, as in the puzzle.
+    pub fn run(&self, data: &str) -> Result<usize, String> {
+        let mut caves: Map = data.parse()?;
+        if self.with_infinite_floor {
+            caves = caves.with_infinite_floor();
+        }
+        if let FrameOutput::PpmFrames(directory) = &self.output {
+            std::fs::create_dir_all(directory).map_err(|e| format!("{e}"))?;
+        }
+        let grains_per_frame = self.grains_per_frame.max(1);
+        let mut sandfall = Sandfall::new(&mut caves);
+        let mut frame_limiter = None;
+        let mut num_grains = 0;
+        let mut focus = sandfall.start;
+        let mut frame_index = 0;
+        while let Some(sand_position) = sandfall.next() {
+            num_grains += 1;
+            focus = sand_position;
+            if num_grains % grains_per_frame == 0 {
+                self.render_frame(
+                    sandfall.caves(),
+                    focus,
+                    frame_index,
+                    num_grains,
+                    &mut frame_limiter,
+                )?;
+                frame_index += 1;
+            }
+        }
+        if num_grains % grains_per_frame != 0 {
+            self.render_frame(
+                sandfall.caves(),
+                focus,
+                frame_index,
+                num_grains,
+                &mut frame_limiter,
+            )?;
+        }
+        Ok(num_grains)
+    }
 }
 
 type Parsed = Map;
@@ -282,4 +471,60 @@ mod tests {
     fn test_part2() {
         assert_eq!(part2(&input()), SOLUTION_PART2)
     }
+    #[test]
+    fn test_diagonal_rock_paths() {
+        let point = |col, row| Point { row, col };
+        assert_eq!(
+            point(498, 4).line_to(point(495, 7)),
+            vec![point(498, 4), point(497, 5), point(496, 6), point(495, 7)]
+        );
+        assert_eq!(
+            point(0, 0).line_to(point(4, 2)),
+            vec![
+                point(0, 0),
+                point(1, 1),
+                point(2, 1),
+                point(3, 2),
+                point(4, 2)
+            ]
+        );
+        // Sand rules are unchanged, so sand slips diagonally through 45° walls: only the grain
+        // landing in the middle of this cup's floor comes to rest
+        assert_eq!(part1(&parse_input("496,2 -> 499,5 -> 501,5 -> 504,2")), 1);
+    }
+    #[test]
+    fn test_malformed_rock_paths() {
+        assert_eq!(
+            "498,4 -> 498,x".parse::<Map>().err(),
+            Some("Line 1: Invalid row in point 498,x: invalid digit found in string".to_string())
+        );
+        assert_eq!(
+            "498,4 -> 498,6\n4986".parse::<Map>().err(),
+            Some("Line 2: Failed to parse point: 4986".to_string())
+        );
+        assert_eq!(
+            "500,600 -> 501,600".parse::<Map>().err(),
+            Some(
+                "Rock at row 600 is too deep: the floor below would extend left of column 0"
+                    .to_string()
+            )
+        );
+        assert!("500,498 -> 501,498".parse::<Map>().is_ok());
+    }
+    #[test]
+    fn test_visualisation_given_example_input() {
+        let directory = std::env::temp_dir().join("aoc_2022_day14_frames");
+        let visualisation = Visualisation {
+            with_infinite_floor: false,
+            grains_per_frame: 5,
+            output: FrameOutput::PpmFrames(directory.clone()),
+        };
+        let num_grains = visualisation
+            .run(include_str!("../example/2022/day14.txt"))
+            .unwrap();
+        assert_eq!(num_grains, EXAMPLE_SOLUTION_PART1);
+        // 24 grains at 5 per frame: 4 full frames and a final partial one
+        assert!(directory.join("frame_00004.ppm").exists());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
 }
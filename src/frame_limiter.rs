@@ -0,0 +1,30 @@
+use std::io::Write;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+// Animates frames in the terminal, drawing at most `max_fps` of them per second
+pub struct FrameLimiter {
+    frame_duration: Duration,
+    last_frame: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(max_fps: u32) -> Self {
+        Self {
+            frame_duration: Duration::from_secs_f64(1. / max_fps.max(1) as f64),
+            last_frame: Instant::now(),
+        }
+    }
+    // Wait until the next frame is due, then replace the terminal's contents with this frame
+    pub fn draw_frame(&mut self, frame: &str) -> Result<(), String> {
+        sleep(
+            self.frame_duration
+                .saturating_sub(self.last_frame.elapsed()),
+        );
+        self.last_frame = Instant::now();
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "\x1b[H\x1b[2J{frame}")
+            .and_then(|_| stdout.flush())
+            .map_err(|e| format!("{e}"))
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub type Rgb = [u8; 3];
+
+// Blow each pixel of a width x height image up to a square of scale x scale pixels
+pub fn scale_pixels<T: Copy>(width: usize, height: usize, scale: usize, pixels: &[T]) -> Vec<T> {
+    assert_eq!(pixels.len(), width * height);
+    let scaled_width = width * scale;
+    (0..scaled_width * height * scale)
+        .map(|position| {
+            let (row, col) = (
+                position / scaled_width / scale,
+                position % scaled_width / scale,
+            );
+            pixels[row * width + col]
+        })
+        .collect()
+}
+
+// Binary portable pixmap: trivial to write and readable by most image viewers
+pub fn write_ppm(path: &Path, width: usize, height: usize, pixels: &[Rgb]) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    encode_ppm(&mut file, width, height, pixels)?;
+    file.flush()
+}
+
+fn encode_ppm(
+    out: &mut impl Write,
+    width: usize,
+    height: usize,
+    pixels: &[Rgb],
+) -> std::io::Result<()> {
+    assert_eq!(pixels.len(), width * height);
+    write!(out, "P6\n{width} {height}\n255\n")?;
+    for pixel in pixels {
+        out.write_all(pixel)?;
+    }
+    Ok(())
+}
+
+// Binary portable bitmap, with lit pixels in black
+pub fn write_pbm(path: &Path, width: usize, height: usize, pixels: &[bool]) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    encode_pbm(&mut file, width, height, pixels)?;
+    file.flush()
+}
+
+fn encode_pbm(
+    out: &mut impl Write,
+    width: usize,
+    height: usize,
+    pixels: &[bool],
+) -> std::io::Result<()> {
+    assert_eq!(pixels.len(), width * height);
+    write!(out, "P4\n{width} {height}\n")?;
+    // Each row is packed into bytes, most significant bit first
+    for row in pixels.chunks(width) {
+        for byte in row.chunks(8) {
+            let bits = byte
+                .iter()
+                .enumerate()
+                .fold(0, |bits, (index, lit)| bits | (*lit as u8) << (7 - index));
+            out.write_all(&[bits])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_pixels() {
+        assert_eq!(
+            scale_pixels(2, 1, 2, &['a', 'b']),
+            vec!['a', 'a', 'b', 'b', 'a', 'a', 'b', 'b']
+        );
+        assert!(scale_pixels(2, 1, 0, &['a', 'b']).is_empty());
+    }
+    #[test]
+    fn test_encode_ppm() {
+        let mut image = Vec::new();
+        encode_ppm(&mut image, 2, 1, &[[1, 2, 3], [4, 5, 6]]).unwrap();
+        assert_eq!(image, b"P6\n2 1\n255\n\x01\x02\x03\x04\x05\x06");
+    }
+    #[test]
+    fn test_encode_pbm() {
+        // Rows are padded to a whole number of bytes
+        let row = [
+            true, true, false, false, true, false, true, false, false, true,
+        ];
+        let mut image = Vec::new();
+        encode_pbm(&mut image, 10, 2, &[row, row].concat()).unwrap();
+        assert_eq!(image, b"P4\n10 2\n\xca\x40\xca\x40");
+    }
+    #[test]
+    fn test_write_ppm() {
+        let path = std::env::temp_dir().join(format!(
+            "advent_of_code_2022_image_writer_{}.ppm",
+            std::process::id()
+        ));
+        let written = write_ppm(&path, 1, 1, &[[255, 0, 0]]).and_then(|_| std::fs::read(&path));
+        // Clean up before asserting, so that a failure doesn't leave the file behind
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written.unwrap(), b"P6\n1 1\n255\n\xff\x00\x00");
+    }
+}
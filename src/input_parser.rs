@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::str::FromStr;
 
 pub fn parse_line_vec<T>(s: &str) -> Result<Vec<T>, <T as FromStr>::Err>
@@ -16,3 +16,67 @@ where
 {
     s.split(sep).map(|line| line.parse()).collect()
 }
+
+// A position in a string, for hand written recursive descent parsers.
+// Whitespace is allowed between any two tokens.
+pub struct ByteCursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteCursor<'a> {
+    pub fn new(s: &'a str) -> Self {
+        Self {
+            bytes: s.as_bytes(),
+            position: 0,
+        }
+    }
+    // Skip whitespace and return the next byte, without consuming it
+    pub fn peek(&mut self) -> Option<u8> {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        self.bytes.get(self.position).copied()
+    }
+    pub fn advance(&mut self, num_bytes: usize) {
+        self.position += num_bytes;
+    }
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+    pub fn error(&self, expected: &str) -> String {
+        match self.bytes.get(self.position) {
+            Some(b) => format!(
+                "Expected {expected} at position {}, found '{}'",
+                self.position, *b as char
+            ),
+            None => format!(
+                "Expected {expected} at position {}, found end of input",
+                self.position
+            ),
+        }
+    }
+    pub fn parse_unsigned<T>(&mut self) -> Result<T, String>
+    where
+        T: FromStr,
+        <T as FromStr>::Err: Display,
+    {
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|b| b.is_ascii_digit())
+        {
+            self.position += 1;
+        }
+        // Only ascii digits were consumed, so this slice is valid utf8
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .unwrap()
+            .parse()
+            .map_err(|e| format!("Invalid integer at position {start}: {e}"))
+    }
+}
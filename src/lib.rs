@@ -2,6 +2,7 @@
 #![feature(iter_array_chunks)]
 #![feature(associated_type_bounds)]
 #![feature(iter_intersperse)]
+#![cfg_attr(test, feature(test))]
 
 #[macro_use]
 extern crate scan_rules;
@@ -33,6 +34,10 @@ pub mod day_16;
 //pub mod day_23;
 //pub mod day_24;
 //pub mod day_25;
+mod frame_limiter;
+mod image_writer;
 mod input_parser;
+#[cfg(test)]
+mod random;
 
 aoc_lib! { year = 2022 }